@@ -0,0 +1,128 @@
+//! Benchmarks for menus built out of 100k lines, comparing the trie-backed operations with
+//! scanning every entry and with rebuilding the menu from scratch.
+
+#![allow(dead_code, unused_imports)]
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+
+#[path = "../src/cli/mod.rs"]
+mod cli;
+#[path = "../src/matcher.rs"]
+mod matcher;
+#[path = "../src/menu.rs"]
+mod menu;
+#[path = "../src/numeric.rs"]
+mod numeric;
+#[path = "../src/trie.rs"]
+mod trie;
+
+use cli::MatchMode;
+use matcher::{CaseMatching, Matcher};
+use menu::Menu;
+
+const LINE_COUNT: usize = 100_000;
+const MATCHER: Matcher = Matcher {
+    mode: MatchMode::Prefix,
+    case: CaseMatching::Smart,
+};
+
+/// Generates directory listing-like lines, which share long prefixes.
+fn generate_lines(count: usize) -> Vec<String> {
+    const WORDS: [&str; 8] = [
+        "src", "docs", "target", "share", "lib", "bin", "etc", "local",
+    ];
+    let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+
+    (0..count)
+        .map(|idx| {
+            let mut line = String::new();
+
+            for _ in 0..3 {
+                seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+                line.push('/');
+                line.push_str(WORDS[(seed >> 33) as usize % WORDS.len()]);
+            }
+
+            line.push_str(&format!("/file{idx}.txt"));
+            line
+        })
+        .collect()
+}
+
+fn build_menu(lines: &[String]) -> Menu {
+    let mut menu = Menu::new(MATCHER);
+
+    for line in lines {
+        menu.push(line.clone(), None);
+    }
+
+    menu
+}
+
+fn bench_build(c: &mut Criterion) {
+    let lines = generate_lines(LINE_COUNT);
+    let (old_lines, new_lines) = lines.split_at(LINE_COUNT - 1000);
+    let menu = build_menu(old_lines);
+
+    let mut group = c.benchmark_group("build");
+    group.sample_size(10);
+
+    group.bench_function("100k lines from scratch", |b| {
+        b.iter(|| build_menu(black_box(&lines)))
+    });
+
+    group.bench_function("append 1k lines", |b| {
+        b.iter_batched(
+            || menu.clone(),
+            |mut menu| {
+                for line in new_lines {
+                    menu.push(line.clone(), None);
+                }
+                menu
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.finish();
+}
+
+fn bench_queries(c: &mut Criterion) {
+    let lines = generate_lines(LINE_COUNT);
+    let menu = build_menu(&lines);
+    // Matches nothing, which is the worst case for scanning.
+    let input = "/local/etc/bin/x";
+
+    let mut group = c.benchmark_group("queries");
+
+    group.bench_function("has_selectable (trie)", |b| {
+        b.iter(|| menu.has_selectable(black_box(input)))
+    });
+
+    group.bench_function("has_selectable (scan)", |b| {
+        b.iter(|| {
+            menu.entries()
+                .iter()
+                .any(|entry| entry.is_selectable(black_box(input), MATCHER))
+        })
+    });
+
+    group.bench_function("find_acceptable (trie)", |b| {
+        b.iter(|| menu.find_acceptable(black_box(input)))
+    });
+
+    group.bench_function("find_acceptable (scan)", |b| {
+        b.iter(|| {
+            menu.entries()
+                .iter()
+                .position(|entry| entry.is_acceptable(black_box(input), MATCHER))
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_build, bench_queries);
+criterion_main!(benches);
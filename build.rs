@@ -6,17 +6,47 @@ use std::{
 use clap::CommandFactory;
 use clap_complete::Shell;
 use cli::Args;
+use roff::{bold, roman, Roff};
 
 #[path = "src/cli/args.rs"]
 mod cli;
 
+fn render_exit_status_section(cmd: &clap::Command, w: &mut dyn io::Write) -> io::Result<()> {
+    let Some(help) = cmd.get_after_long_help() else {
+        return Ok(());
+    };
+
+    let mut roff = Roff::new();
+    roff.control("SH", ["EXIT STATUS"]);
+
+    // Skip the "Exit status:" heading, which is only needed for --help.
+    for line in help.to_string().lines().skip(1) {
+        if let Some((code, description)) = line.trim().split_once(' ') {
+            roff.control("TP", []);
+            roff.text([bold(code)]);
+            roff.text([roman(description.trim())]);
+        }
+    }
+
+    roff.to_writer(w)
+}
+
 fn generate_man_pages(out_dir: &Path, bin: &str) -> io::Result<()> {
     let out_dir = out_dir.join("man");
 
     let cmd = Args::command();
-    let man = clap_mangen::Man::new(cmd);
+    let man = clap_mangen::Man::new(cmd.clone());
     let mut buf = vec![];
-    man.render(&mut buf)?;
+
+    // Same as Man::render, but with a proper EXIT STATUS section instead of EXTRA.
+    man.render_title(&mut buf)?;
+    man.render_name_section(&mut buf)?;
+    man.render_synopsis_section(&mut buf)?;
+    man.render_description_section(&mut buf)?;
+    man.render_options_section(&mut buf)?;
+    render_exit_status_section(&cmd, &mut buf)?;
+    man.render_version_section(&mut buf)?;
+    man.render_authors_section(&mut buf)?;
 
     fs::create_dir_all(&out_dir)?;
     fs::write(out_dir.join(format!("{bin}.1")), buf)?;
@@ -1,177 +1,881 @@
-use std::io::{self, Write};
+use std::{
+    collections::VecDeque,
+    env,
+    io::{self, Read, Write},
+    iter, mem,
+    ops::Range,
+    os::fd::AsFd,
+    sync::mpsc::{Receiver, RecvError, TryRecvError},
+    time::{Duration, Instant},
+};
 
 use crossterm::{
     cursor,
-    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+        KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+    },
     execute, queue,
-    style::{self, StyledContent, Stylize},
+    style::{self, ContentStyle, StyledContent, Stylize},
     terminal::{self, ClearType},
 };
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 use crate::{
-    cli::Args,
-    ct_extra,
-    menu::{Entry, Menu, SearchDirection},
-    string,
+    cli::{Action, Args, CliError, Key, Layout, MatchMode, Overflow, PromptPosition},
+    color::ColorDepth,
+    ct_extra, fields,
+    keymap::{self, Keymap},
+    matcher::{CaseMatching, Matcher},
+    menu::{self, Entry, KeySet, Menu, SearchDirection},
+    numeric, source, string,
+    theme::Highlights,
 };
 
+/// An entry split into styled segments, along with the columns covered by its highlights.
+#[derive(Debug, Default)]
+struct StyledEntry {
+    segments: Vec<StyledContent<String>>,
+    width: usize,
+    highlighted: Option<Range<usize>>,
+}
+
+impl StyledEntry {
+    fn push(&mut self, style: ContentStyle, content: &str, highlighted: bool) {
+        if content.is_empty() {
+            return;
+        }
+
+        // Line breaks would throw off the layout, so they're shown as symbols instead.
+        let content = string::show_line_breaks(content).into_owned();
+        let start = self.width;
+        self.width += content.width();
+
+        if highlighted {
+            let start = self.highlighted.as_ref().map_or(start, |range| range.start);
+            self.highlighted = Some(start..self.width);
+        }
+
+        self.segments.push(StyledContent::new(style, content));
+    }
+}
+
+/// A row of the view, showing all or part of an entry.
+struct EntryRow {
+    idx: usize,
+    /// Whether this is the first row of the entry, which shows its indicator and marker.
+    first: bool,
+    segments: Vec<StyledContent<String>>,
+}
+
+/// Everything that makes up the entries, so that they can be set aside during a reload.
+pub struct Entries {
+    lines: Vec<String>,
+    keys: Vec<Option<String>>,
+    key_set: KeySet,
+    menu: Menu,
+}
+
+impl Entries {
+    fn new(matcher: Matcher) -> Self {
+        Self {
+            lines: Vec::new(),
+            keys: Vec::new(),
+            key_set: KeySet::new(matcher.case != CaseMatching::Respect),
+            menu: Menu::new(matcher),
+        }
+    }
+}
+
+/// How often to check for new lines while the input is still being read.
+const LOADING_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Minimum time between redraws while the input is still being read.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Maximum amount of lines to add to the menu at once.
+const MAX_LINES_PER_BATCH: usize = 10_000;
+
 pub struct App {
     pub args: Args,
+    pub lines: Vec<String>,
+    /// Explicit key declared by each line, if any.
+    pub keys: Vec<Option<String>>,
+    pub key_set: KeySet,
     pub menu: Menu,
+    pub keymap: Keymap,
+    pub highlights: Highlights,
+    /// Where lines come from, until all of them have been read.
+    pub source: Option<Receiver<Result<String, CliError>>>,
+    /// Entries from before a reload, while the lines replacing them are being read.
+    pub reloading: Option<Entries>,
+    /// Line that was selected before reloading, to be selected again once it comes back.
+    pub reselect: Option<String>,
     pub input: String,
-    pub exit_value: Option<Option<String>>,
+    pub scroll: usize,
+    pub height: usize,
+    pub top_row: u16,
+    /// Entry shown on each row of the view, if any. Entries may span several rows when
+    /// wrapping.
+    pub row_entries: Vec<Option<usize>>,
+    /// Shown next to the input until the next key press, such as why a reload failed.
+    pub message: Option<String>,
+    pub exit_value: Option<Result<Vec<String>, CliError>>,
 }
 
 impl App {
-    pub fn new(args: Args, lines: &[String]) -> Self {
+    pub fn new(args: Args, source: Receiver<Result<String, CliError>>) -> Self {
+        let matcher = Matcher {
+            mode: args.match_mode,
+            case: CaseMatching::from_flags(args.ignore_case, args.smart_case),
+        };
+
+        let color_depth = ColorDepth::detect(args.color, |name| env::var(name).ok());
+
+        // Colors are already adapted to the terminal, so crossterm shouldn't drop them on
+        // its own when NO_COLOR is set.
+        style::Colored::set_ansi_color_disabled(false);
+
         Self {
+            key_set: KeySet::new(matcher.case != CaseMatching::Respect),
+            menu: Menu::new(matcher),
+            keymap: Keymap::new(&args.bind),
+            highlights: Highlights::new(&args).downgrade(color_depth),
+            source: Some(source),
+            reloading: None,
+            reselect: None,
+            message: None,
             args,
-            menu: Menu::from_lines(lines),
+            lines: Vec::new(),
+            keys: Vec::new(),
             input: String::new(),
+            scroll: 0,
+            height: 0,
+            top_row: 0,
+            row_entries: Vec::new(),
             exit_value: None,
         }
     }
 
-    pub fn init(tty: &mut impl Write) -> io::Result<()> {
+    pub fn init(tty: &mut impl Write, fullscreen: bool) -> io::Result<()> {
         terminal::enable_raw_mode()?;
-        execute!(tty, cursor::Hide)?;
+
+        if fullscreen {
+            execute!(tty, terminal::EnterAlternateScreen, cursor::MoveTo(0, 0))?;
+        }
+
+        execute!(tty, cursor::Hide, EnableMouseCapture)?;
         Ok(())
     }
 
-    pub fn deinit(tty: &mut impl Write) -> io::Result<()> {
+    pub fn deinit(tty: &mut impl Write, fullscreen: bool) -> io::Result<()> {
         execute!(
             tty,
+            DisableMouseCapture,
             cursor::Show,
             terminal::Clear(ClearType::FromCursorDown)
         )?;
 
+        if fullscreen {
+            execute!(tty, terminal::LeaveAlternateScreen)?;
+        }
+
         terminal::disable_raw_mode()?;
         Ok(())
     }
 
-    pub fn run(&mut self, tty: &mut impl Write) -> io::Result<Option<String>> {
+    pub fn run(&mut self, tty: &mut (impl Read + Write + AsFd)) -> Result<Vec<String>, CliError> {
         let mut redraw = true;
+        (_, self.top_row) = ct_extra::cursor_position(tty)?;
+
+        let mut last_draw: Option<Instant> = None;
 
         loop {
-            if redraw {
+            // Lines may come in faster than they can be drawn, so redraws are throttled
+            // until all of them have been read.
+            let draw_due = self.source.is_none()
+                || last_draw.is_none_or(|last_draw| last_draw.elapsed() >= REDRAW_INTERVAL);
+
+            if redraw && draw_due {
                 self.draw(tty)?;
+                redraw = false;
+                last_draw = Some(Instant::now());
             }
 
-            redraw = self.handle_events()?;
+            let received = self.receive_lines();
+            redraw |= received;
+
+            // Events are read on every iteration, so that the picker can be used (and
+            // aborted) even while lines keep coming in. Only block on them once there's
+            // nothing else to do.
+            if self.exit_value.is_none() {
+                let timeout = if received {
+                    Some(Duration::ZERO)
+                } else if self.source.is_some() || redraw {
+                    Some(LOADING_POLL_INTERVAL)
+                } else {
+                    None
+                };
+
+                redraw |= self.handle_events(timeout)?;
+            }
 
             if let Some(value) = self.exit_value.take() {
-                break Ok(value);
+                break value;
             }
         }
     }
 
-    fn draw(&self, tty: &mut impl Write) -> io::Result<()> {
-        for (i, entry) in self.menu.entries().iter().enumerate() {
-            queue!(
-                tty,
-                terminal::Clear(ClearType::CurrentLine),
-                style::Print("  "),
-            )?;
+    /// Blocks until the first line comes in, so that the terminal is left alone when there's
+    /// no input at all.
+    pub fn wait_for_input(&mut self) -> Result<(), CliError> {
+        let Some(source) = &self.source else {
+            return Ok(());
+        };
 
-            for el in self.stylize_entry(entry) {
-                queue!(tty, style::Print(el))?;
+        match source.recv() {
+            Ok(Ok(line)) => self.add_lines(vec![line]),
+            Ok(Err(err)) => return Err(err),
+            Err(RecvError) => return Err(CliError::NoInput),
+        }
+
+        match self.exit_value.take() {
+            Some(Err(err)) => Err(err),
+            _ => Ok(()),
+        }
+    }
+
+    /// Adds whatever lines have arrived since the last call to the menu. Returns whether
+    /// anything changed.
+    fn receive_lines(&mut self) -> bool {
+        let Some(source) = &self.source else {
+            return false;
+        };
+
+        let mut new_lines = Vec::new();
+        let finished = loop {
+            // Leave the rest for later, so that events still get a turn.
+            if new_lines.len() >= MAX_LINES_PER_BATCH {
+                break false;
+            }
+
+            match source.try_recv() {
+                Ok(Ok(line)) => new_lines.push(line),
+                Ok(Err(err)) if self.reloading.is_some() => {
+                    self.undo_reload(err.to_string());
+                    return true;
+                }
+                Ok(Err(err)) => {
+                    self.exit_value = Some(Err(err));
+                    return false;
+                }
+                Err(TryRecvError::Empty) => break false,
+                Err(TryRecvError::Disconnected) => break true,
             }
+        };
 
-            queue!(tty, cursor::MoveToColumn(0))?;
+        let changed = !new_lines.is_empty() || finished;
+
+        if !new_lines.is_empty() {
+            self.add_lines(new_lines);
+        }
 
-            if i < self.menu.len() - 1 {
-                queue!(tty, style::Print("\n"))?;
+        if finished && self.exit_value.is_none() {
+            self.source = None;
+
+            if self.reloading.is_some() {
+                self.finish_reload();
+            } else if self.menu.len() == 0 {
+                self.exit_value = Some(Err(CliError::NoInput));
+            } else if !self.input.is_empty() && !self.args.no_auto_accept {
+                // Auto-accepting was held back until now.
+                self.try_auto_accept();
+            }
+        }
+
+        changed
+    }
+
+    fn add_lines(&mut self, new_lines: Vec<String>) {
+        let old_len = self.lines.len();
+
+        for line in new_lines {
+            let (key, line) = if self.args.explicit_keys || self.args.key_delimiter.is_some() {
+                menu::split_explicit_key(&line, self.args.key_delimiter.as_deref())
+            } else {
+                (None, line)
+            };
+
+            if let Some(key) = &key {
+                if let Err(other_key) = self.key_set.insert(key) {
+                    self.exit_value = Some(Err(CliError::KeyConflict(
+                        other_key.to_string(),
+                        key.clone(),
+                    )));
+                    return;
+                }
             }
+
+            self.keys.push(key);
+            self.lines.push(line);
+        }
+
+        let display_lines: Vec<_> = self.lines[old_len..]
+            .iter()
+            .map(|line| {
+                fields::select_fields(line, self.args.delimiter.as_deref(), &self.args.with_nth)
+            })
+            .collect();
+
+        for (line, key) in display_lines.into_iter().zip(&self.keys[old_len..]) {
+            self.menu.push(line, key.clone());
         }
 
+        if self.args.match_mode == MatchMode::Hint {
+            self.assign_hints();
+        }
+
+        if let Some(reselect) = &self.reselect {
+            if let Some(idx) = (old_len..self.lines.len()).find(|&idx| &self.lines[idx] == reselect)
+            {
+                self.menu.select(idx, &self.input);
+                self.reselect = None;
+            }
+        }
+
+        self.menu.update_selection(&self.input);
+    }
+
+    /// Replaces every entry with the output of the reload command, as it comes in.
+    fn reload(&mut self) -> bool {
+        let Some(command) = &self.args.reload_command else {
+            return false;
+        };
+
+        match source::spawn_command_reader(command, self.args.read0) {
+            Ok(source) => self.source = Some(source),
+            Err(err) => {
+                self.message = Some(format!("could not run the reload command: {err}"));
+                return true;
+            }
+        }
+
+        // A reload that's still going is cut short, but what it replaced is kept around.
+        if self.reloading.is_none() {
+            self.reselect = self
+                .menu
+                .selection()
+                .map(|&selection| self.lines[selection].clone());
+        }
+
+        let previous = self.replace_entries(Entries::new(self.menu.matcher()));
+        self.reloading.get_or_insert(previous);
+        self.scroll = 0;
+        true
+    }
+
+    /// Brings back the entries from before the reload, which failed with `message`.
+    fn undo_reload(&mut self, message: String) {
+        if let Some(previous) = self.reloading.take() {
+            self.replace_entries(previous);
+        }
+
+        self.source = None;
+        self.reselect = None;
+        self.message = Some(message);
+        self.keep_matching_input();
+    }
+
+    fn finish_reload(&mut self) {
+        self.reloading = None;
+        self.reselect = None;
+        self.keep_matching_input();
+    }
+
+    /// Clears the input if it no longer matches anything, as can happen when the entries
+    /// are replaced.
+    fn keep_matching_input(&mut self) {
+        if !self.menu.has_selectable(&self.input) {
+            self.input.clear();
+            self.menu.update_selection(&self.input);
+        }
+    }
+
+    fn replace_entries(&mut self, entries: Entries) -> Entries {
+        Entries {
+            lines: mem::replace(&mut self.lines, entries.lines),
+            keys: mem::replace(&mut self.keys, entries.keys),
+            key_set: mem::replace(&mut self.key_set, entries.key_set),
+            menu: mem::replace(&mut self.menu, entries.menu),
+        }
+    }
+
+    /// Gives a hint to every entry without a declared key. Hints get longer as entries
+    /// come in, so the existing ones may change too.
+    fn assign_hints(&mut self) {
+        // Hints are just generated keys. If they run out, the remaining entries fall back
+        // to regular prefixes.
+        let alphabet: Vec<_> = self.args.hint_alphabet.chars().collect();
+        let taken_keys: Vec<_> = self.key_set.compared_keys().collect();
+        let missing = self.keys.len() - taken_keys.len();
+        let mut hints = menu::generate_hints(missing, &alphabet, &taken_keys).into_iter();
+
+        for idx in 0..self.keys.len() {
+            if self.keys[idx].is_none() {
+                let hint = hints.next();
+
+                if self.menu.entries()[idx].key != hint {
+                    self.menu.set_key(idx, hint);
+                }
+            }
+        }
+    }
+
+    fn prompt_rows(&self) -> usize {
+        if self.args.prompt.is_some() || self.message.is_some() {
+            1
+        } else {
+            0
+        }
+    }
+
+    fn max_entry_rows(&self, term_rows: u16) -> usize {
+        let max_height = match self.args.height {
+            Some(height) if !self.args.fullscreen => height.min(term_rows),
+            _ => term_rows,
+        } as usize;
+
+        max_height.saturating_sub(self.prompt_rows()).max(1)
+    }
+
+    /// Width taken up by the indicator and marker columns before each entry.
+    fn lead_width(&self) -> usize {
+        let marker_width = if self.args.multi {
+            self.args.marker.width().unwrap_or(0)
+        } else {
+            0
+        };
+
+        self.args.indicator.width().unwrap_or(0) + marker_width + 1
+    }
+
+    fn first_entry_row(&self) -> usize {
+        // Reversing the layout flips the prompt over to the other side as well.
+        match (self.args.prompt_position, self.args.layout) {
+            (PromptPosition::Top, Layout::Default) | (PromptPosition::Bottom, Layout::Reverse) => {
+                self.prompt_rows()
+            }
+            _ => 0,
+        }
+    }
+
+    /// The direction through the menu that goes down the screen.
+    fn downwards(&self) -> SearchDirection {
+        match self.args.layout {
+            Layout::Default => SearchDirection::Forwards,
+            Layout::Reverse => SearchDirection::Backwards,
+        }
+    }
+
+    /// Lays out the entries in view, scrolling just enough for the selection to fit in
+    /// `max_rows` rows.
+    fn layout_rows(&mut self, max_rows: usize, max_width: usize) -> Vec<EntryRow> {
+        let len = self.menu.len();
+
+        if len == 0 {
+            self.scroll = 0;
+            return Vec::new();
+        }
+
+        let mut scroll = self.scroll.min(len - 1);
         if let Some(&selection) = self.menu.selection() {
-            ct_extra::queue_move_up_exact(tty, (self.menu.len() - selection - 1) as u16)?;
+            scroll = numeric::scroll_to_fit(scroll, selection, max_rows);
+        }
+
+        let mut shown = VecDeque::new();
+        let mut rows = 0;
+
+        // Entries are laid out from the selection upwards first, so that it stays in view
+        // even if the ones before it take up several rows.
+        let anchor = self.menu.selection().copied().unwrap_or(scroll);
+
+        for idx in (scroll..=anchor).rev() {
+            let lines = self.layout_entry(idx, max_width);
+
+            if rows + lines.len() > max_rows && idx != anchor {
+                scroll = idx + 1;
+                break;
+            }
+
+            rows += lines.len();
+            shown.push_front((idx, lines));
+        }
+
+        for idx in anchor + 1..len {
+            if rows >= max_rows {
+                break;
+            }
+
+            let lines = self.layout_entry(idx, max_width);
+            rows += lines.len();
+            shown.push_back((idx, lines));
+        }
+
+        // Near the end of the menu, entries before the scroll offset fill up the view.
+        while scroll > 0 {
+            let lines = self.layout_entry(scroll - 1, max_width);
+
+            if rows + lines.len() > max_rows {
+                break;
+            }
+
+            scroll -= 1;
+            rows += lines.len();
+            shown.push_front((scroll, lines));
+        }
+
+        self.scroll = scroll;
+
+        shown
+            .into_iter()
+            .flat_map(|(idx, lines)| {
+                lines
+                    .into_iter()
+                    .enumerate()
+                    .map(move |(line, segments)| EntryRow {
+                        idx,
+                        first: line == 0,
+                        segments,
+                    })
+            })
+            .take(max_rows)
+            .collect()
+    }
+
+    /// Splits an entry into the rows it's drawn on, none of them wider than `max_width`.
+    fn layout_entry(&self, idx: usize, max_width: usize) -> Vec<Vec<StyledContent<String>>> {
+        let entry = &self.menu.entries()[idx];
+        let styled = self.stylize_entry(entry);
+        let focus = styled.highlighted.unwrap_or_default();
+
+        match self.args.overflow {
+            Overflow::Truncate => {
+                // The prefix is what picks the entry, so the ellipsis never covers it.
+                let keep_width = if entry.matches_by_prefix(self.menu.matcher().mode) {
+                    focus.end
+                } else {
+                    0
+                };
+                vec![string::truncate_segments(
+                    styled.segments,
+                    max_width,
+                    keep_width,
+                )]
+            }
+            Overflow::Scroll => vec![string::scroll_segments(styled.segments, focus, max_width)],
+            Overflow::Wrap => string::wrap_segments(styled.segments, max_width),
+        }
+    }
+
+    fn draw(&mut self, tty: &mut impl Write) -> io::Result<()> {
+        let (term_cols, term_rows) = terminal::size()?;
+        let rows = self.layout_rows(
+            self.max_entry_rows(term_rows),
+            (term_cols as usize).saturating_sub(self.lead_width()),
+        );
+
+        // The menu is empty until the first lines come in, but it still takes up a row.
+        let height = if self.args.fullscreen {
+            self.max_entry_rows(term_rows)
+        } else {
+            rows.len().max(1)
+        };
+        self.height = height;
+
+        // Rows left blank go on the side away from the prompt. When reversed, the rows of
+        // each wrapped entry still read from top to bottom.
+        let blank_rows = iter::repeat_n(None, height - rows.len());
+        let view: Vec<_> = match self.args.layout {
+            Layout::Default => rows.iter().map(Some).chain(blank_rows).collect(),
+            Layout::Reverse => blank_rows
+                .chain(
+                    rows.chunk_by(|a, b| a.idx == b.idx)
+                        .rev()
+                        .flatten()
+                        .map(Some),
+                )
+                .collect(),
+        };
+        self.row_entries = view.iter().map(|row| row.map(|row| row.idx)).collect();
+
+        let total_rows = height + self.prompt_rows();
+        let first_entry_row = self.first_entry_row();
+        self.reserve_rows(tty, total_rows as u16, term_rows)?;
+
+        // Everything below the anchor row is cleared at once, including rows left over from
+        // a taller menu.
+        queue!(
+            tty,
+            cursor::MoveTo(0, self.top_row),
+            terminal::Clear(ClearType::FromCursorDown)
+        )?;
+
+        for row in 0..total_rows {
+            queue!(tty, cursor::MoveTo(0, self.top_row + row as u16))?;
+
+            if (first_entry_row..first_entry_row + height).contains(&row) {
+                if let Some(Some(entry_row)) = view.get(row - first_entry_row) {
+                    self.queue_entry_row(tty, entry_row)?;
+                }
+            } else {
+                self.queue_prompt(tty, term_cols as usize)?;
+            }
+        }
+
+        // The cursor is left at the anchor row, so that the menu can be cleared from there
+        // on exit, even after a panic.
+        queue!(tty, cursor::MoveTo(0, self.top_row))?;
+        tty.flush()?;
+        Ok(())
+    }
+
+    /// Makes room for `rows` rows starting at the anchor row, scrolling the terminal up by
+    /// just as many rows as the menu would otherwise go past its bottom.
+    fn reserve_rows(&mut self, tty: &mut impl Write, rows: u16, term_rows: u16) -> io::Result<()> {
+        // The anchor row can't be past the bottom of the terminal, whatever happened to it.
+        self.top_row = self.top_row.min(term_rows.saturating_sub(1));
+        let overflow = (self.top_row + rows.min(term_rows)).saturating_sub(term_rows);
+
+        if overflow > 0 {
+            // Newlines on the bottom row push the shell's output up into the scrollback.
             queue!(
                 tty,
-                style::PrintStyledContent(self.args.indicator.with(self.args.hl_indicator)),
-                cursor::MoveToColumn(0),
+                cursor::MoveTo(0, term_rows - 1),
+                style::Print("\n".repeat(overflow as usize))
             )?;
-            ct_extra::queue_move_up_exact(tty, selection as u16)?;
-        } else {
-            ct_extra::queue_move_up_exact(tty, (self.menu.len() - 1) as u16)?;
+            self.top_row -= overflow;
         }
 
-        tty.flush()?;
         Ok(())
     }
 
-    fn stylize_entry(&self, entry: &Entry) -> Vec<StyledContent<impl std::fmt::Display>> {
-        if !entry.is_selectable(&self.input) {
-            vec![entry.body.clone().with(self.args.hl_disabled_entry)]
+    fn queue_entry_row(&self, tty: &mut impl Write, row: &EntryRow) -> io::Result<()> {
+        // Rows wrapped off an entry are indented to line up with its first row.
+        if !row.first {
+            queue!(tty, style::Print(" ".repeat(self.lead_width())))?;
         } else {
-            let input_seg = self
-                .input
-                .to_string()
-                .with(self.args.hl_input_overlay)
-                .bold();
-
-            if self.input.len() >= entry.prefix_len {
-                vec![
-                    input_seg,
-                    entry.body[self.input.len()..].to_string().stylize(),
-                ]
+            if self.menu.selection() == Some(&row.idx) {
+                queue!(
+                    tty,
+                    style::PrintStyledContent(StyledContent::new(
+                        self.highlights.indicator,
+                        self.args.indicator
+                    ))
+                )?;
             } else {
-                vec![
-                    input_seg,
-                    entry.body[self.input.len()..entry.prefix_len]
-                        .to_string()
-                        .with(self.args.hl_prefix)
-                        .bold(),
-                    entry.body[entry.prefix_len..].to_string().stylize(),
-                ]
+                queue!(tty, style::Print(" "))?;
             }
+
+            if self.args.multi {
+                if self.menu.is_marked(row.idx) {
+                    queue!(
+                        tty,
+                        style::PrintStyledContent(StyledContent::new(
+                            self.highlights.marker,
+                            self.args.marker
+                        ))
+                    )?;
+                } else {
+                    queue!(tty, style::Print(" "))?;
+                }
+            }
+
+            queue!(tty, style::Print(" "))?;
         }
+
+        for el in &row.segments {
+            queue!(
+                tty,
+                style::PrintStyledContent(StyledContent::new(*el.style(), el.content()))
+            )?;
+        }
+
+        Ok(())
     }
 
-    fn handle_events(&mut self) -> io::Result<bool> {
-        if let Event::Key(KeyEvent {
-            kind: KeyEventKind::Press,
-            modifiers,
-            code,
-            ..
-        }) = event::read()?
-        {
-            let redraw = match (modifiers, code) {
-                (KeyModifiers::NONE, KeyCode::Esc)
-                | (KeyModifiers::CONTROL, KeyCode::Char('c')) => {
-                    self.exit_value = Some(None);
-                    false
-                }
-                (KeyModifiers::NONE, KeyCode::Char(ch)) => self.input_type(ch),
-                (KeyModifiers::NONE, KeyCode::Enter) => self.try_manual_accept(),
-                (KeyModifiers::NONE, KeyCode::Backspace) => self.input_delete_char(),
-                (KeyModifiers::CONTROL, KeyCode::Char('w')) => self.input_delete_word(),
-                (KeyModifiers::CONTROL, KeyCode::Char('n'))
-                | (KeyModifiers::NONE, KeyCode::Tab) => {
-                    self.move_selection(SearchDirection::Forwards)
-                }
-                (KeyModifiers::CONTROL, KeyCode::Char('p'))
-                | (KeyModifiers::SHIFT, KeyCode::BackTab) => {
-                    self.move_selection(SearchDirection::Backwards)
-                }
-                _ => false,
+    fn queue_prompt(&self, tty: &mut impl Write, max_width: usize) -> io::Result<()> {
+        let prompt = self.args.prompt.clone().unwrap_or_default();
+
+        // The terminal cursor is hidden while the menu is open, so a fake one is drawn
+        // right after the input instead.
+        let mut segments = vec![
+            prompt.stylize(),
+            self.input.clone().stylize(),
+            " ".to_string().reverse(),
+        ];
+
+        if let Some(message) = &self.message {
+            segments.push(StyledContent::new(
+                self.highlights.indicator,
+                format!("  {message}"),
+            ));
+        }
+
+        for el in string::truncate_segments(segments, max_width, 0) {
+            queue!(tty, style::PrintStyledContent(el))?;
+        }
+
+        Ok(())
+    }
+
+    fn stylize_entry(&self, entry: &Entry) -> StyledEntry {
+        let mut styled = StyledEntry::default();
+
+        let Some(matched) = entry.find_match(&self.input, self.menu.matcher()) else {
+            let text = match &entry.key {
+                Some(key) => format!("{key} {}", entry.body),
+                None => entry.body.clone(),
             };
-            Ok(redraw)
-        } else {
-            Ok(false)
+            styled.push(self.highlights.disabled_entry, &text, false);
+            return styled;
+        };
+
+        let target = entry.target();
+        let mut styled_len = 0;
+
+        for range in matched {
+            styled.push(ContentStyle::new(), &target[styled_len..range.start], false);
+            styled.push(self.highlights.input_overlay, &target[range.clone()], true);
+            styled_len = range.end;
         }
+
+        if entry.matches_by_prefix(self.menu.matcher().mode) && styled_len < entry.prefix_len {
+            styled.push(
+                self.highlights.prefix,
+                &target[styled_len..entry.prefix_len],
+                true,
+            );
+            styled_len = entry.prefix_len;
+        }
+
+        styled.push(ContentStyle::new(), &target[styled_len..], false);
+
+        // Entries with an explicit key show it right before their body.
+        if entry.key.is_some() {
+            styled.push(ContentStyle::new(), &format!(" {}", entry.body), false);
+        }
+
+        styled
+    }
+
+    /// Handles the next event, waiting for at most `timeout` if given, or for as long as it
+    /// takes otherwise. Returns whether anything changed.
+    fn handle_events(&mut self, timeout: Option<Duration>) -> io::Result<bool> {
+        if let Some(timeout) = timeout {
+            if !event::poll(timeout)? {
+                return Ok(false);
+            }
+        }
+
+        match event::read()? {
+            Event::Key(KeyEvent {
+                kind: KeyEventKind::Press,
+                modifiers,
+                code,
+                ..
+            }) => Ok(self.handle_key(modifiers, code)),
+            Event::Mouse(mouse_event) => Ok(self.handle_mouse(mouse_event)),
+            Event::Resize(_, term_rows) => {
+                // Terminals push their contents up when they shrink, taking the menu with
+                // them, so it's moved up to where it can still fit.
+                let rows = (self.height + self.prompt_rows()) as u16;
+                self.top_row = self.top_row.min(term_rows.saturating_sub(rows));
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn handle_key(&mut self, modifiers: KeyModifiers, code: KeyCode) -> bool {
+        let key = Key { modifiers, code };
+
+        // Messages go away on the next key press.
+        let had_message = self.message.take().is_some();
+
+        let changed = match self.keymap.get(key) {
+            Some(action) => self.perform(action),
+            None => match keymap::normalize(key) {
+                Key {
+                    modifiers: KeyModifiers::NONE,
+                    code: KeyCode::Char(ch),
+                } => self.input_type(ch),
+                _ => false,
+            },
+        };
+
+        changed || had_message
+    }
+
+    fn perform(&mut self, action: Action) -> bool {
+        match action {
+            Action::Accept => self.try_manual_accept(),
+            Action::Abort => {
+                self.exit_value = Some(Err(CliError::Interrupted));
+                false
+            }
+            Action::Next => self.move_selection(SearchDirection::Forwards),
+            Action::Prev => self.move_selection(SearchDirection::Backwards),
+            Action::First => self.menu.select_end(&self.input, SearchDirection::Forwards),
+            Action::Last => self
+                .menu
+                .select_end(&self.input, SearchDirection::Backwards),
+            Action::Up => self.move_selection(self.downwards().reverse()),
+            Action::Down => self.move_selection(self.downwards()),
+            Action::PageDown => self.move_page(self.downwards()),
+            Action::PageUp => self.move_page(self.downwards().reverse()),
+            Action::DeleteChar => self.input_delete_char(),
+            Action::DeleteWord => self.input_delete_word(),
+            Action::ClearQuery => self.input_clear(),
+            Action::Toggle if self.args.multi => self.toggle_selection_mark(),
+            Action::Reload => self.reload(),
+            Action::Toggle | Action::Ignore => false,
+        }
+    }
+
+    fn handle_mouse(&mut self, mouse_event: MouseEvent) -> bool {
+        match mouse_event.kind {
+            MouseEventKind::Down(MouseButton::Left) => self
+                .entry_at_row(mouse_event.row)
+                .is_some_and(|idx| self.click_entry(idx)),
+            MouseEventKind::ScrollDown => self.move_selection(self.downwards()),
+            MouseEventKind::ScrollUp => self.move_selection(self.downwards().reverse()),
+            _ => false,
+        }
+    }
+
+    fn entry_at_row(&self, screen_row: u16) -> Option<usize> {
+        let row =
+            (screen_row.checked_sub(self.top_row)? as usize).checked_sub(self.first_entry_row())?;
+
+        self.row_entries.get(row).copied().flatten()
     }
 
     fn input_type(&mut self, ch: char) -> bool {
         let mut new_input = self.input.clone();
         new_input.push(ch);
 
-        if !self.args.unrestricted_input && !self.menu.has_selectable(&new_input) {
+        // Whatever was typed is checked against the new entries once the reload is done.
+        if !self.args.unrestricted_input
+            && self.reloading.is_none()
+            && !self.menu.has_selectable(&new_input)
+        {
             false
         } else {
             self.input = new_input;
 
-            if !self.args.no_auto_accept {
+            if !self.args.no_auto_accept
+                && (self.source.is_none() || self.args.accept_while_loading)
+            {
                 self.try_auto_accept();
             }
 
@@ -198,23 +902,88 @@ impl App {
         }
     }
 
+    fn input_clear(&mut self) -> bool {
+        if self.input.is_empty() {
+            false
+        } else {
+            self.input.clear();
+            true
+        }
+    }
+
     fn move_selection(&mut self, direction: SearchDirection) -> bool {
         self.menu
             .move_selection(&self.input, direction, !self.args.no_wrap)
     }
 
-    fn try_manual_accept(&mut self) -> bool {
-        if let Some(entry) = self.menu.manual_accept() {
-            self.exit_value = Some(Some(entry.body.clone()));
+    /// Moves the selection by as many selectable entries as fit in the view, stopping at
+    /// either end.
+    fn move_page(&mut self, direction: SearchDirection) -> bool {
+        let mut moved = false;
+
+        for _ in 0..self.height {
+            if !self.menu.move_selection(&self.input, direction, false) {
+                break;
+            }
+            moved = true;
+        }
+
+        moved
+    }
+
+    fn click_entry(&mut self, idx: usize) -> bool {
+        if !self.menu.select(idx, &self.input) {
+            false
+        } else {
+            if self.args.click_accept {
+                self.try_manual_accept();
+            }
+            true
+        }
+    }
+
+    /// Returns what to print for the entry at `idx` if it's accepted.
+    fn output(&self, idx: usize) -> String {
+        fields::select_fields(
+            &self.lines[idx],
+            self.args.delimiter.as_deref(),
+            &self.args.accept_nth,
+        )
+    }
+
+    fn toggle_selection_mark(&mut self) -> bool {
+        if let Some(&selection) = self.menu.selection() {
+            self.menu.toggle_mark(selection);
             true
         } else {
             false
         }
     }
 
+    fn try_manual_accept(&mut self) -> bool {
+        let mut accepted: Vec<_> = self.menu.marked().map(|idx| self.output(idx)).collect();
+
+        if accepted.is_empty() {
+            accepted.extend(self.menu.manual_accept().map(|idx| self.output(idx)));
+        }
+
+        if accepted.is_empty() {
+            false
+        } else {
+            self.exit_value = Some(Ok(accepted));
+            true
+        }
+    }
+
     fn try_auto_accept(&mut self) -> bool {
-        if let Some(accepted_entry) = self.menu.find_acceptable(&self.input) {
-            self.exit_value = Some(Some(accepted_entry.body.clone()));
+        if let Some(idx) = self.menu.find_acceptable(&self.input) {
+            if self.args.multi {
+                self.menu.toggle_mark(idx);
+                self.menu.select(idx, "");
+                self.input.clear();
+            } else {
+                self.exit_value = Some(Ok(vec![self.output(idx)]));
+            }
             true
         } else {
             false
@@ -1,5 +1,10 @@
-use clap::Parser;
-use crossterm::style::Color;
+use std::path::PathBuf;
+
+use clap::{Arg, ArgAction, Parser, ValueEnum};
+use crossterm::{
+    event::{KeyCode, KeyModifiers},
+    style::{Attribute, Color, ContentStyle},
+};
 use derive_more::{Display, Error, From};
 
 #[derive(Debug, Clone, From, Display, Error, PartialEq, Eq)]
@@ -52,13 +57,342 @@ fn parse_highlight(src: &str) -> Result<Color, String> {
     }
 }
 
+/// Parses a space-separated list of attributes ("bold", "italic", "underline" and
+/// "reverse") and colors ("fg=COLOR" and "bg=COLOR"). A bare color sets the foreground, and
+/// "none" stands for no style at all.
+fn parse_style(src: &str) -> Result<ContentStyle, String> {
+    let mut style = ContentStyle::new();
+
+    for token in src.split_whitespace() {
+        match token {
+            "none" => {}
+            "bold" => style.attributes.set(Attribute::Bold),
+            "italic" => style.attributes.set(Attribute::Italic),
+            "underline" => style.attributes.set(Attribute::Underlined),
+            "reverse" => style.attributes.set(Attribute::Reverse),
+            _ => {
+                let invalid = |_| format!("could not parse style {token:?}");
+
+                if let Some(color) = token.strip_prefix("bg=") {
+                    style.background_color = Some(parse_highlight(color).map_err(invalid)?);
+                } else {
+                    let color = token.strip_prefix("fg=").unwrap_or(token);
+                    style.foreground_color = Some(parse_highlight(color).map_err(invalid)?);
+                }
+            }
+        }
+    }
+
+    Ok(style)
+}
+
+/// Same as `parse_style`, except that a lone bare color is also bold. These highlights used to
+/// take just a color, which was always shown in bold.
+fn parse_bold_style(src: &str) -> Result<ContentStyle, String> {
+    let mut style = parse_style(src)?;
+    let mut tokens = src.split_whitespace();
+
+    if let (Some(token), None) = (tokens.next(), tokens.next()) {
+        if !token.contains('=') && parse_highlight(token).is_ok() {
+            style.attributes.set(Attribute::Bold);
+        }
+    }
+
+    Ok(style)
+}
+
+/// A range of 1-based field indices, where negative indices count from the last field.
+/// Missing bounds extend to the first or last field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldRange {
+    pub start: Option<isize>,
+    pub end: Option<isize>,
+}
+
+fn parse_field_index(src: &str) -> Result<isize, String> {
+    match src.parse::<isize>() {
+        Ok(0) | Err(_) => Err("could not parse field index".to_string()),
+        Ok(idx) => Ok(idx),
+    }
+}
+
+fn parse_field_range(src: &str) -> Result<FieldRange, String> {
+    let Some((start, end)) = src.split_once("..") else {
+        let idx = parse_field_index(src)?;
+        return Ok(FieldRange {
+            start: Some(idx),
+            end: Some(idx),
+        });
+    };
+
+    let parse_bound = |bound: &str| {
+        if bound.is_empty() {
+            Ok(None)
+        } else {
+            parse_field_index(bound).map(Some)
+        }
+    };
+
+    Ok(FieldRange {
+        start: parse_bound(start)?,
+        end: parse_bound(end)?,
+    })
+}
+
+/// Drops repeated characters, keeping the first of each, since they would make several
+/// entries share the same hint.
+fn parse_hint_alphabet(src: &str) -> Result<String, String> {
+    let mut alphabet = String::new();
+
+    for c in src.chars() {
+        if !alphabet.contains(c) {
+            alphabet.push(c);
+        }
+    }
+
+    if alphabet.chars().count() < 2 {
+        Err("hint alphabet must have at least two distinct characters".to_string())
+    } else {
+        Ok(alphabet)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum MatchMode {
+    /// Match the input against the start of each entry.
+    Prefix,
+    /// Label each entry with a short hint, and match the input against those instead.
+    Hint,
+    /// Match the input anywhere within each entry.
+    Substring,
+    /// Match the input's characters in order, but not necessarily next to each other.
+    Fuzzy,
+    /// Match the input against the start of any word within each entry.
+    WordPrefix,
+}
+
+/// Something a key can be bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Action {
+    /// Accept the marked entries, or the selected one if none are marked.
+    Accept,
+    /// Close the picker without accepting anything.
+    Abort,
+    /// Select the next selectable entry.
+    Next,
+    /// Select the previous selectable entry.
+    Prev,
+    /// Select the selectable entry shown above the current one, which depends on --layout.
+    Up,
+    /// Select the selectable entry shown below the current one, which depends on --layout.
+    Down,
+    /// Select the first selectable entry.
+    First,
+    /// Select the last selectable entry.
+    Last,
+    /// Move the selection down by a page.
+    PageDown,
+    /// Move the selection up by a page.
+    PageUp,
+    /// Delete the last character of the query.
+    DeleteChar,
+    /// Delete the last word of the query.
+    DeleteWord,
+    /// Delete the whole query.
+    ClearQuery,
+    /// Toggle the mark of the selected entry when using --multi.
+    Toggle,
+    /// Replace the entries with the output of --reload-command.
+    Reload,
+    /// Do nothing, which is useful to unbind a key.
+    Ignore,
+}
+
+/// A key along with the modifiers held down while pressing it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Key {
+    pub modifiers: KeyModifiers,
+    pub code: KeyCode,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyBinding {
+    pub key: Key,
+    pub action: Action,
+}
+
+/// Parses keys such as "ctrl-x", "alt-enter" or "btab", following fzf's names.
+fn parse_key(src: &str) -> Result<Key, String> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut name = src;
+
+    // A trailing "-" is the minus key rather than a separator.
+    while let Some((modifier, rest)) = name.split_once('-').filter(|(_, rest)| !rest.is_empty()) {
+        modifiers |= match modifier {
+            "ctrl" => KeyModifiers::CONTROL,
+            "alt" => KeyModifiers::ALT,
+            "shift" => KeyModifiers::SHIFT,
+            _ => return Err(format!("unknown key modifier {modifier:?}")),
+        };
+        name = rest;
+    }
+
+    let code = match name {
+        "enter" | "return" => KeyCode::Enter,
+        "esc" => KeyCode::Esc,
+        "tab" if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+        "tab" => KeyCode::Tab,
+        "btab" => {
+            modifiers |= KeyModifiers::SHIFT;
+            KeyCode::BackTab
+        }
+        "space" => KeyCode::Char(' '),
+        "bspace" | "backspace" => KeyCode::Backspace,
+        "del" | "delete" => KeyCode::Delete,
+        "insert" => KeyCode::Insert,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pgup" => KeyCode::PageUp,
+        "pgdn" => KeyCode::PageDown,
+        "comma" => KeyCode::Char(','),
+        "colon" => KeyCode::Char(':'),
+        _ => match name
+            .strip_prefix('f')
+            .and_then(|n| n.parse::<u8>().ok())
+            .filter(|n| (1..=12).contains(n))
+        {
+            Some(n) => KeyCode::F(n),
+            None => {
+                let mut chars = name.chars();
+
+                match (chars.next(), chars.next()) {
+                    (Some(ch), None) => KeyCode::Char(ch),
+                    _ => return Err(format!("unknown key {name:?}")),
+                }
+            }
+        },
+    };
+
+    Ok(Key { modifiers, code })
+}
+
+/// Parses a binding such as "ctrl-j:next".
+fn parse_key_binding(src: &str) -> Result<KeyBinding, String> {
+    // Action names never have colons, unlike the colon key.
+    let (key, action) = src
+        .rsplit_once(':')
+        .ok_or_else(|| format!("expected KEY:ACTION, got {src:?}"))?;
+
+    Ok(KeyBinding {
+        key: parse_key(key)?,
+        action: Action::from_str(action, false)
+            .map_err(|_| format!("unknown action {action:?}"))?,
+    })
+}
+
+/// A built-in set of highlights.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Theme {
+    /// 256-color highlights for dark backgrounds.
+    Dark,
+    /// 256-color highlights for light backgrounds.
+    Light,
+    /// No colors at all, only attributes like bold and underline.
+    Mono,
+    /// Highlights using the terminal's own 16-color palette.
+    #[value(name = "16color")]
+    SixteenColor,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// Use colors unless NO_COLOR is set or the terminal can't show them.
+    Auto,
+    /// Always use colors.
+    Always,
+    /// Never use colors.
+    Never,
+}
+
+/// Which way the menu is laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Layout {
+    /// Show the first entry at the top.
+    Default,
+    /// Show the first entry at the bottom, with the menu growing upwards from the prompt.
+    Reverse,
+}
+
+/// How to show entries wider than the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Overflow {
+    /// Cut entries short, ending them with an ellipsis.
+    Truncate,
+    /// Continue entries on as many rows as they need.
+    Wrap,
+    /// Show the part of each entry around the matched text.
+    Scroll,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PromptPosition {
+    Top,
+    Bottom,
+}
+
+/// Lets flags take an optional value, as in `--multi=false`, so that flags set by the config
+/// file or PPICK_DEFAULT_OPTS can be turned off again.
+fn accept_flag_value(arg: Arg) -> Arg {
+    if arg.get_action().takes_values() || arg.get_id() == "no_config" {
+        return arg;
+    }
+
+    arg.action(ArgAction::Set)
+        .num_args(0..=1)
+        .require_equals(true)
+        .default_value("false")
+        .default_missing_value("true")
+        .value_name("BOOL")
+        .hide_default_value(true)
+        .hide_possible_values(true)
+}
+
+/// Follows fzf's convention. Keep in sync with `CliError::exit_code`.
+const EXIT_STATUS_HELP: &str = "\
+Exit status:
+  0    An entry was selected.
+  1    No entry could be selected, e.g. because the input was empty.
+  2    An error occurred.
+  130  The picker was interrupted.";
+
 #[derive(Debug, Parser)]
-#[command(version, about, long_about = None)]
+#[command(
+    version,
+    about,
+    long_about = None,
+    after_long_help = EXIT_STATUS_HELP,
+    args_override_self = true,
+    mut_args = accept_flag_value
+)]
 pub struct Args {
+    /// Ignore the config file and PPICK_DEFAULT_OPTS, which otherwise provide defaults for
+    /// every other option.
+    #[arg(long)]
+    pub no_config: bool,
+
     /// Whether to clear the screen before showing the picker.
     #[arg(short, long)]
     pub clear: bool,
 
+    /// Show the picker on the alternate screen, taking up the whole terminal regardless of
+    /// --height. The screen is left exactly as it was once the picker closes.
+    #[arg(long)]
+    pub fullscreen: bool,
+
     /// Whether to allow wrapping the current selection.
     #[arg(long)]
     pub no_wrap: bool,
@@ -67,34 +401,164 @@ pub struct Args {
     #[arg(long)]
     pub no_auto_accept: bool,
 
+    /// Allow auto-accepting entries before all of the input has been read.
+    #[arg(long)]
+    pub accept_while_loading: bool,
+
+    /// Accept an entry as soon as it's clicked, instead of just selecting it.
+    #[arg(long)]
+    pub click_accept: bool,
+
     /// Allow the user to type in anything, even if it doesn't match any menu entry
     /// anymore.
     #[arg(short, long)]
     pub unrestricted_input: bool,
 
+    /// Maximum number of rows to use for the menu. Defaults to the terminal height.
+    #[arg(long, value_parser = clap::value_parser!(u16).range(1..))]
+    pub height: Option<u16>,
+
+    /// Show a prompt line with the given text, followed by the current query.
+    #[arg(short, long)]
+    pub prompt: Option<String>,
+
+    /// Which way to lay out the menu. Reversing it also flips the prompt to the other side,
+    /// so that it stays next to the first entry.
+    #[arg(long, value_enum, default_value_t = Layout::Default)]
+    pub layout: Layout,
+
+    /// Where to show the prompt line, relative to the menu.
+    #[arg(long, value_enum, default_value_t = PromptPosition::Top)]
+    pub prompt_position: PromptPosition,
+
+    /// Allow marking several entries (with Ctrl-Space) and accepting all of them at once.
+    /// Auto-accepting an entry toggles its mark and clears the input instead.
+    #[arg(short, long)]
+    pub multi: bool,
+
+    /// Read entries from a file instead of stdin.
+    #[arg(long, value_name = "PATH")]
+    pub input_file: Option<PathBuf>,
+
+    /// Read entries from the output of a shell command instead of stdin.
+    #[arg(long, value_name = "CMD", conflicts_with = "input_file")]
+    pub command: Option<String>,
+
+    /// Shell command whose output replaces the entries when pressing Ctrl-R, e.g. to pick
+    /// among things that come and go.
+    #[arg(long, value_name = "CMD")]
+    pub reload_command: Option<String>,
+
+    /// Read input entries delimited by NUL characters instead of newlines.
+    #[arg(long)]
+    pub read0: bool,
+
+    /// Print accepted entries delimited by NUL characters instead of newlines.
+    #[arg(long)]
+    pub print0: bool,
+
+    /// String used to split entries into fields. Defaults to runs of whitespace.
+    #[arg(short, long)]
+    pub delimiter: Option<String>,
+
+    /// Fields to display and match against, e.g. "2", "-1", "2..", "1,3..4". Defaults to
+    /// the whole entry.
+    #[arg(long, value_delimiter = ',', value_parser = parse_field_range)]
+    pub with_nth: Vec<FieldRange>,
+
+    /// Fields to print when an entry is accepted, using the same syntax as --with-nth.
+    /// Defaults to the whole entry.
+    #[arg(long, value_delimiter = ',', value_parser = parse_field_range)]
+    pub accept_nth: Vec<FieldRange>,
+
+    /// How to match the input against entries.
+    #[arg(long, value_enum, default_value_t = MatchMode::Prefix)]
+    pub match_mode: MatchMode,
+
+    /// Characters used to generate hints when using "--match-mode hint". Repeated characters
+    /// are ignored.
+    #[arg(long, value_parser = parse_hint_alphabet, default_value = "asdfghjkl")]
+    pub hint_alphabet: String,
+
+    /// Match entries case-insensitively. Prefixes are then unique regardless of case.
+    #[arg(long)]
+    pub ignore_case: bool,
+
+    /// Match entries case-insensitively, unless the input has uppercase characters.
+    #[arg(long, conflicts_with = "ignore_case")]
+    pub smart_case: bool,
+
+    /// Let entries declare their own key, as in "[g] git status". Declared keys take
+    /// priority over generated prefixes.
+    #[arg(short = 'k', long)]
+    pub explicit_keys: bool,
+
+    /// Declare explicit keys as "<KEY><DELIMITER><ENTRY>" instead. Implies --explicit-keys.
+    #[arg(long)]
+    pub key_delimiter: Option<String>,
+
+    /// How to show entries wider than the terminal.
+    #[arg(long, value_enum, default_value_t = Overflow::Truncate)]
+    pub overflow: Overflow,
+
     /// Character used to indicate the current selection.
     #[arg(short, long, default_value_t = '▌')]
     pub indicator: char,
 
+    /// Character used to indicate marked entries when using --multi.
+    #[arg(long, default_value_t = '+')]
+    pub marker: char,
+
+    /// Bind keys to actions, as in "ctrl-j:next,ctrl-k:prev". These take priority over the
+    /// default bindings.
+    ///
+    /// Keys are named like "ctrl-x", "alt-x", "shift-up", "enter", "esc", "tab", "btab",
+    /// "space", "bspace", "del", "up", "down", "left", "right", "home", "end", "pgup",
+    /// "pgdn", "f1" to "f12", "comma" and "colon", or are a single character.
+    ///
+    /// Actions are "accept", "abort", "next", "prev", "up", "down", "first", "last",
+    /// "page-down", "page-up", "delete-char", "delete-word", "clear-query", "toggle",
+    /// "reload" and "ignore".
+    #[arg(long, value_name = "KEY:ACTION", value_delimiter = ',', value_parser = parse_key_binding)]
+    pub bind: Vec<KeyBinding>,
+
     /// Whether to silence error messages.
     #[arg(short, long)]
     pub silent: bool,
 
-    /// Highlight for each entry's prefix segment.
-    #[arg(long, value_parser = parse_highlight, default_value = "blue")]
-    pub hl_prefix: Color,
+    /// Built-in theme setting every highlight at once. Each --hl-* option overrides a single
+    /// highlight of the theme.
+    #[arg(long, value_enum, default_value_t = Theme::SixteenColor)]
+    pub theme: Theme,
+
+    /// When to use colors. Colors are downgraded to what the terminal supports, going by
+    /// COLORTERM and TERM.
+    #[arg(long, value_enum, value_name = "WHEN", default_value_t = ColorChoice::Auto)]
+    pub color: ColorChoice,
+
+    /// Highlight for each entry's prefix segment. Highlights are a space-separated list of
+    /// "bold", "italic", "underline", "reverse", "fg=COLOR" and "bg=COLOR", as in
+    /// "bold underline fg=blue bg=#202020", where a bare color sets the foreground. A lone
+    /// bare color is shown in bold, as in "blue".
+    #[arg(long, value_name = "STYLE", value_parser = parse_bold_style)]
+    pub hl_prefix: Option<ContentStyle>,
 
-    /// Highlight for the input overlay over selectable entries.
-    #[arg(long, value_parser = parse_highlight, default_value = "red")]
-    pub hl_input_overlay: Color,
+    /// Highlight for the input overlay over selectable entries. A lone bare color is shown in
+    /// bold, as with --hl-prefix.
+    #[arg(long, value_name = "STYLE", value_parser = parse_bold_style)]
+    pub hl_input_overlay: Option<ContentStyle>,
 
     /// Highlight for non-selectable entries.
-    #[arg(long, value_parser = parse_highlight, default_value = "bright-black")]
-    pub hl_disabled_entry: Color,
+    #[arg(long, value_name = "STYLE", value_parser = parse_style)]
+    pub hl_disabled_entry: Option<ContentStyle>,
 
     /// Highlight for the indicator of the current selection.
-    #[arg(long, value_parser = parse_highlight, default_value = "red")]
-    pub hl_indicator: Color,
+    #[arg(long, value_name = "STYLE", value_parser = parse_style)]
+    pub hl_indicator: Option<ContentStyle>,
+
+    /// Highlight for the marker of marked entries.
+    #[arg(long, value_name = "STYLE", value_parser = parse_style)]
+    pub hl_marker: Option<ContentStyle>,
 }
 
 #[cfg(test)]
@@ -137,6 +601,198 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_style_valid() {
+        let style = |fg, bg, attributes: &[Attribute]| ContentStyle {
+            foreground_color: fg,
+            background_color: bg,
+            underline_color: None,
+            attributes: attributes
+                .iter()
+                .fold(Default::default(), |acc, &attr| acc | attr),
+        };
+
+        assert_eq!(
+            parse_style("blue"),
+            Ok(style(Some(Color::DarkBlue), None, &[]))
+        );
+        assert_eq!(parse_style("none"), Ok(style(None, None, &[])));
+        assert_eq!(
+            parse_style("bold underline fg=blue bg=#202020"),
+            Ok(style(
+                Some(Color::DarkBlue),
+                Some(Color::Rgb {
+                    r: 0x20,
+                    g: 0x20,
+                    b: 0x20
+                }),
+                &[Attribute::Bold, Attribute::Underlined]
+            ))
+        );
+        assert_eq!(
+            parse_style(" italic  reverse bg=8 "),
+            Ok(style(
+                None,
+                Some(Color::AnsiValue(8)),
+                &[Attribute::Italic, Attribute::Reverse]
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_style_invalid() {
+        assert!(parse_style("blod").is_err());
+        assert!(parse_style("fg=").is_err());
+        assert!(parse_style("bg=reddish").is_err());
+        assert!(parse_style("bold fg=256").is_err());
+
+        assert_eq!(
+            parse_style("bg=reddish"),
+            Err("could not parse style \"bg=reddish\"".to_string())
+        );
+        assert_eq!(
+            parse_style("fg=reddish"),
+            Err("could not parse style \"fg=reddish\"".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_bold_style() {
+        assert_eq!(parse_bold_style("blue"), parse_style("bold blue"));
+        assert_eq!(parse_bold_style("#80aec3"), parse_style("bold #80aec3"));
+        assert_eq!(parse_bold_style("fg=blue"), parse_style("fg=blue"));
+        assert_eq!(parse_bold_style("italic blue"), parse_style("italic blue"));
+        assert_eq!(parse_bold_style("none"), Ok(ContentStyle::new()));
+        assert!(parse_bold_style("reddish").is_err());
+    }
+
+    #[test]
+    fn test_parse_field_range_valid() {
+        assert_eq!(
+            parse_field_range("2"),
+            Ok(FieldRange {
+                start: Some(2),
+                end: Some(2)
+            })
+        );
+        assert_eq!(
+            parse_field_range("-1"),
+            Ok(FieldRange {
+                start: Some(-1),
+                end: Some(-1)
+            })
+        );
+        assert_eq!(
+            parse_field_range("2.."),
+            Ok(FieldRange {
+                start: Some(2),
+                end: None
+            })
+        );
+        assert_eq!(
+            parse_field_range("..-2"),
+            Ok(FieldRange {
+                start: None,
+                end: Some(-2)
+            })
+        );
+        assert_eq!(
+            parse_field_range("1..3"),
+            Ok(FieldRange {
+                start: Some(1),
+                end: Some(3)
+            })
+        );
+        assert_eq!(
+            parse_field_range(".."),
+            Ok(FieldRange {
+                start: None,
+                end: None
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_field_range_invalid() {
+        assert!(parse_field_range("").is_err());
+        assert!(parse_field_range("0").is_err());
+        assert!(parse_field_range("1..0").is_err());
+        assert!(parse_field_range("a..2").is_err());
+        assert!(parse_field_range("1...2").is_err());
+    }
+
+    #[test]
+    fn test_parse_hint_alphabet() {
+        assert_eq!(parse_hint_alphabet("asdf"), Ok("asdf".to_string()));
+        assert_eq!(parse_hint_alphabet("aab"), Ok("ab".to_string()));
+        assert_eq!(parse_hint_alphabet("sasdfd"), Ok("sadf".to_string()));
+        assert!(parse_hint_alphabet("").is_err());
+        assert!(parse_hint_alphabet("aaa").is_err());
+    }
+
+    #[test]
+    fn test_parse_key_binding_valid() {
+        let binding = |modifiers, code, action| KeyBinding {
+            key: Key { modifiers, code },
+            action,
+        };
+
+        assert_eq!(
+            parse_key_binding("ctrl-j:next"),
+            Ok(binding(
+                KeyModifiers::CONTROL,
+                KeyCode::Char('j'),
+                Action::Next
+            ))
+        );
+        assert_eq!(
+            parse_key_binding("ctrl-alt-x:clear-query"),
+            Ok(binding(
+                KeyModifiers::CONTROL | KeyModifiers::ALT,
+                KeyCode::Char('x'),
+                Action::ClearQuery
+            ))
+        );
+        assert_eq!(
+            parse_key_binding("shift-tab:prev"),
+            Ok(binding(KeyModifiers::SHIFT, KeyCode::BackTab, Action::Prev))
+        );
+        assert_eq!(
+            parse_key_binding("btab:prev"),
+            Ok(binding(KeyModifiers::SHIFT, KeyCode::BackTab, Action::Prev))
+        );
+        assert_eq!(
+            parse_key_binding("f5:reload"),
+            Ok(binding(KeyModifiers::NONE, KeyCode::F(5), Action::Reload))
+        );
+        assert_eq!(
+            parse_key_binding("::accept"),
+            Ok(binding(
+                KeyModifiers::NONE,
+                KeyCode::Char(':'),
+                Action::Accept
+            ))
+        );
+        assert_eq!(
+            parse_key_binding("alt--:page-up"),
+            Ok(binding(
+                KeyModifiers::ALT,
+                KeyCode::Char('-'),
+                Action::PageUp
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_key_binding_invalid() {
+        assert!(parse_key_binding("ctrl-j").is_err());
+        assert!(parse_key_binding("ctrl-j:jump").is_err());
+        assert!(parse_key_binding("meta-j:next").is_err());
+        assert!(parse_key_binding("enterr:accept").is_err());
+        assert!(parse_key_binding("f13:accept").is_err());
+        assert!(parse_key_binding(":accept").is_err());
+    }
+
     #[test]
     fn test_parse_hex_invalid() {
         assert_eq!(parse_hex(""), Err(ParseHexError::InvalidLength));
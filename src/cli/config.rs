@@ -0,0 +1,313 @@
+use std::{
+    env,
+    ffi::{OsStr, OsString},
+    fs,
+    io::ErrorKind,
+    iter,
+    path::{Path, PathBuf},
+};
+
+use clap::{parser::ValueSource, Arg, ArgMatches, CommandFactory, FromArgMatches};
+use toml_edit::{ImDocument, Item, Value};
+
+use super::{Args, CliError};
+
+const BIN_NAME: &str = "ppick";
+const DEFAULT_OPTS_VAR: &str = "PPICK_DEFAULT_OPTS";
+
+fn config_path() -> Option<PathBuf> {
+    let config_dir = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+    Some(config_dir.join(BIN_NAME).join("config.toml"))
+}
+
+/// Returns the first line of a clap error without the "error: " tag, which is enough to
+/// be shown next to where the bad value came from.
+fn clap_message(err: &clap::Error) -> String {
+    let rendered = err.to_string();
+    let first_line = rendered.lines().next().unwrap_or_default();
+    first_line
+        .strip_prefix("error: ")
+        .unwrap_or(first_line)
+        .to_string()
+}
+
+fn line_at(contents: &str, offset: usize) -> usize {
+    contents[..offset].matches('\n').count() + 1
+}
+
+fn scalar_value(value: &Value) -> Option<String> {
+    match value {
+        Value::String(value) => Some(value.value().clone()),
+        Value::Integer(value) => Some(value.value().to_string()),
+        _ => None,
+    }
+}
+
+/// Whether `arg` is a flag, which may still take an optional value to turn it off.
+fn is_flag(arg: &Arg) -> bool {
+    !arg.get_action().takes_values()
+        || arg
+            .get_num_args()
+            .is_some_and(|num_args| num_args.min_values() == 0)
+}
+
+/// Turns the contents of a config file into command-line arguments. Each key is a long
+/// option, with booleans standing for flags and arrays for options given several times.
+/// Errors come with the line they were found on.
+fn config_args(contents: &str) -> Result<Vec<String>, (usize, String)> {
+    let document = ImDocument::parse(contents).map_err(|err| {
+        let line = err.span().map_or(1, |span| line_at(contents, span.start));
+        (line, err.message().to_string())
+    })?;
+
+    let command = Args::command();
+    let mut args = Vec::new();
+
+    for (name, item) in document.iter() {
+        let line = document
+            .key(name)
+            .and_then(|key| key.span())
+            .map_or(1, |span| line_at(contents, span.start));
+
+        let arg = command
+            .get_arguments()
+            .find(|arg| arg.get_long() == Some(name) && name != "no-config")
+            .ok_or_else(|| (line, format!("unknown key {name:?}")))?;
+
+        let Item::Value(value) = item else {
+            return Err((line, format!("expected a value for {name:?}")));
+        };
+
+        if is_flag(arg) {
+            match value {
+                Value::Boolean(enabled) if *enabled.value() => args.push(format!("--{name}")),
+                Value::Boolean(_) => {}
+                _ => return Err((line, format!("expected a boolean for {name:?}"))),
+            }
+            continue;
+        }
+
+        let values = match value {
+            Value::Array(array) => array.iter().map(scalar_value).collect(),
+            _ => scalar_value(value).map(|value| vec![value]),
+        }
+        .ok_or_else(|| (line, format!("expected a string or integer for {name:?}")))?;
+
+        for value in values {
+            let arg = format!("--{name}={value}");
+
+            // Each value is checked on its own, so that errors point at the right line.
+            parse_layer([&arg]).map_err(|err| (line, clap_message(&err)))?;
+            args.push(arg);
+        }
+    }
+
+    Ok(args)
+}
+
+fn read_config_file(path: &Path) -> Result<Option<ArgMatches>, CliError> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(CliError::Config(format!("{}: {err}", path.display()))),
+    };
+
+    let args = config_args(&contents).map_err(|(line, message)| {
+        CliError::Config(format!("{}:{line}: {message}", path.display()))
+    })?;
+
+    // Keys are valid on their own, but may still conflict with each other.
+    parse_layer(args)
+        .map(Some)
+        .map_err(|err| CliError::Config(format!("{}: {}", path.display(), clap_message(&err))))
+}
+
+fn default_opts_args() -> Result<Option<ArgMatches>, CliError> {
+    let Ok(opts) = env::var(DEFAULT_OPTS_VAR) else {
+        return Ok(None);
+    };
+
+    let args = shlex::split(&opts)
+        .ok_or_else(|| CliError::Config(format!("{DEFAULT_OPTS_VAR}: unbalanced quotes")))?;
+
+    parse_layer(args)
+        .map(Some)
+        .map_err(|err| CliError::Config(format!("{DEFAULT_OPTS_VAR}: {}", clap_message(&err))))
+}
+
+fn parse_layer(
+    args: impl IntoIterator<Item = impl Into<OsString> + Clone>,
+) -> Result<ArgMatches, clap::Error> {
+    let args = args.into_iter().map(Into::into);
+    Args::command().try_get_matches_from(iter::once(OsString::from(BIN_NAME)).chain(args))
+}
+
+fn conflicts(command: &clap::Command, a: &Arg, b: &Arg) -> bool {
+    let conflicts_with = |a, b: &Arg| {
+        command
+            .get_arg_conflicts_with(a)
+            .iter()
+            .any(|arg| arg.get_id() == b.get_id())
+    };
+
+    conflicts_with(a, b) || conflicts_with(b, a)
+}
+
+/// Merges the options given in each layer of arguments, in increasing order of precedence.
+/// An option given in a later layer replaces its whole value from earlier ones, along with
+/// any options it conflicts with.
+fn merge_layers(layers: &[ArgMatches]) -> Result<Args, clap::Error> {
+    let command = Args::command();
+    let mut merged: Vec<(&Arg, Vec<&OsStr>)> = Vec::new();
+
+    for matches in layers {
+        for arg in command.get_arguments() {
+            let id = arg.get_id().as_str();
+
+            if matches.value_source(id) != Some(ValueSource::CommandLine) {
+                continue;
+            }
+
+            merged.retain(|(other, _)| other.get_id() != id && !conflicts(&command, arg, other));
+            merged.push((arg, matches.get_raw(id).into_iter().flatten().collect()));
+        }
+    }
+
+    let mut args = Vec::new();
+
+    for (arg, values) in merged {
+        let long = arg.get_long().expect("every option has a long name");
+
+        if !arg.get_action().takes_values() {
+            args.push(OsString::from(format!("--{long}")));
+            continue;
+        }
+
+        for value in values {
+            let mut arg = OsString::from(format!("--{long}="));
+            arg.push(value);
+            args.push(arg);
+        }
+    }
+
+    Args::from_arg_matches(&parse_layer(args)?)
+}
+
+/// Parses the command-line arguments on top of the ones from the config file and from
+/// `PPICK_DEFAULT_OPTS`, in increasing order of precedence. Exits on invalid command-line
+/// arguments, like `Args::parse`.
+pub fn parse_args() -> Result<Args, CliError> {
+    let cli_matches = Args::command().get_matches();
+    let mut layers = Vec::new();
+
+    if !cli_matches.get_flag("no_config") {
+        layers.extend(
+            config_path()
+                .map(|path| read_config_file(&path))
+                .transpose()?
+                .flatten(),
+        );
+        layers.extend(default_opts_args()?);
+    }
+
+    layers.push(cli_matches);
+    Ok(merge_layers(&layers).unwrap_or_else(|err| err.exit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::FieldRange;
+
+    #[test]
+    fn test_config_args_valid() {
+        let contents = r##"
+            multi = true
+            no-wrap = false
+            height = 10
+            hl-prefix = "#80aec3"
+            bind = ["ctrl-j:next", "ctrl-k:prev"]
+        "##;
+
+        assert_eq!(
+            config_args(contents),
+            Ok(vec![
+                "--multi".to_string(),
+                "--height=10".to_string(),
+                "--hl-prefix=#80aec3".to_string(),
+                "--bind=ctrl-j:next".to_string(),
+                "--bind=ctrl-k:prev".to_string(),
+            ])
+        );
+    }
+
+    fn merge(layers: &[&[&str]]) -> Args {
+        let layers: Vec<_> = layers
+            .iter()
+            .map(|args| parse_layer(args.iter()).unwrap())
+            .collect();
+
+        merge_layers(&layers).unwrap()
+    }
+
+    #[test]
+    fn test_merge_layers_keeps_earlier_options() {
+        let args = merge(&[&["--height=5", "--multi"], &["--prompt=> "], &[]]);
+
+        assert_eq!(args.height, Some(5));
+        assert!(args.multi);
+        assert_eq!(args.prompt.as_deref(), Some("> "));
+    }
+
+    #[test]
+    fn test_merge_layers_replaces_lists() {
+        let args = merge(&[
+            &["--with-nth=1,2", "--bind=ctrl-j:next"],
+            &["--with-nth", "3"],
+        ]);
+
+        assert_eq!(
+            args.with_nth,
+            [FieldRange {
+                start: Some(3),
+                end: Some(3)
+            }]
+        );
+        assert_eq!(args.bind.len(), 1);
+    }
+
+    #[test]
+    fn test_merge_layers_turns_flags_off() {
+        assert!(!merge(&[&["--multi"], &["--multi=false"]]).multi);
+        assert!(!merge(&[&["-m"], &[], &["--multi=false"]]).multi);
+        assert!(merge(&[&["--multi=false"], &["-m"]]).multi);
+
+        let args = merge(&[&["--ignore-case", "-k"], &["--smart-case"]]);
+        assert!(!args.ignore_case);
+        assert!(args.smart_case);
+        assert!(args.explicit_keys);
+    }
+
+    #[test]
+    fn test_config_args_invalid() {
+        let error_line = |contents| config_args(contents).unwrap_err().0;
+
+        assert_eq!(error_line("multi = true\ncolour = \"red\""), 2);
+        assert_eq!(error_line("multi = true\n\nmulti = \"yes\""), 3);
+        assert_eq!(error_line("\nhl-prefix = \"reddish\""), 2);
+        assert_eq!(error_line("height = 0"), 1);
+        assert_eq!(error_line("height = true"), 1);
+        assert_eq!(error_line("no-config = true"), 1);
+        assert_eq!(error_line("multi = true\n[section]\n"), 2);
+        assert_eq!(error_line("multi = \n"), 1);
+
+        assert_eq!(
+            config_args("colour = \"red\""),
+            Err((1, "unknown key \"colour\"".to_string()))
+        );
+    }
+}
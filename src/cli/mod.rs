@@ -3,8 +3,10 @@ use std::io;
 use derive_more::{Display, Error, From, IsVariant};
 
 mod args;
+mod config;
 
 pub use args::*;
+pub use config::parse_args;
 
 #[derive(Debug, Display, From, Error, IsVariant)]
 pub enum CliError {
@@ -12,6 +14,30 @@ pub enum CliError {
     NoInput,
     #[display("interrupted")]
     Interrupted,
+    #[display("conflicting explicit keys {_0:?} and {_1:?}")]
+    #[from(ignore)]
+    KeyConflict(String, String),
+    #[display("{_0}")]
+    #[from(ignore)]
+    Config(#[error(ignore)] String),
+    #[display("command {_0:?} failed: {_1}")]
+    #[from(ignore)]
+    Command(String, String),
     Io(io::Error),
-    Generic(Box<dyn std::error::Error>),
+    Generic(Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl CliError {
+    /// Follows fzf's convention, and is documented in the man page.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::NoInput => 1,
+            Self::KeyConflict(..)
+            | Self::Config(_)
+            | Self::Command(..)
+            | Self::Io(_)
+            | Self::Generic(_) => 2,
+            Self::Interrupted => 130,
+        }
+    }
 }
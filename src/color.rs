@@ -0,0 +1,216 @@
+use crossterm::style::Color;
+
+use crate::cli::ColorChoice;
+
+/// How many colors the terminal can show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorDepth {
+    None,
+    Ansi16,
+    Ansi256,
+    TrueColor,
+}
+
+/// The default xterm palette, used to find the closest of the 16 basic colors.
+const ANSI_16: [(Color, [u8; 3]); 16] = [
+    (Color::Black, [0, 0, 0]),
+    (Color::DarkRed, [205, 0, 0]),
+    (Color::DarkGreen, [0, 205, 0]),
+    (Color::DarkYellow, [205, 205, 0]),
+    (Color::DarkBlue, [0, 0, 238]),
+    (Color::DarkMagenta, [205, 0, 205]),
+    (Color::DarkCyan, [0, 205, 205]),
+    (Color::Grey, [229, 229, 229]),
+    (Color::DarkGrey, [127, 127, 127]),
+    (Color::Red, [255, 0, 0]),
+    (Color::Green, [0, 255, 0]),
+    (Color::Yellow, [255, 255, 0]),
+    (Color::Blue, [92, 92, 255]),
+    (Color::Magenta, [255, 0, 255]),
+    (Color::Cyan, [0, 255, 255]),
+    (Color::White, [255, 255, 255]),
+];
+
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+impl ColorDepth {
+    /// Works out the color depth from `choice` and the environment, where `var` looks up
+    /// environment variables. Follows the NO_COLOR and CLICOLOR_FORCE conventions.
+    pub fn detect(choice: ColorChoice, var: impl Fn(&str) -> Option<String>) -> Self {
+        let is_set = |name| var(name).is_some_and(|value| !value.is_empty());
+        let term = var("TERM").unwrap_or_default();
+
+        let forced = match choice {
+            ColorChoice::Never => return Self::None,
+            ColorChoice::Always => true,
+            ColorChoice::Auto => {
+                if is_set("NO_COLOR") {
+                    return Self::None;
+                }
+                is_set("CLICOLOR_FORCE") && var("CLICOLOR_FORCE").as_deref() != Some("0")
+            }
+        };
+
+        if matches!(var("COLORTERM").as_deref(), Some("truecolor" | "24bit"))
+            || term.ends_with("-direct")
+        {
+            Self::TrueColor
+        } else if term.contains("256color") {
+            Self::Ansi256
+        } else if term == "dumb" && !forced {
+            Self::None
+        } else {
+            Self::Ansi16
+        }
+    }
+
+    /// Replaces `color` with the closest one the terminal can show, if any.
+    pub fn downgrade(self, color: Color) -> Option<Color> {
+        match (self, color) {
+            (Self::None, _) => None,
+            (Self::TrueColor, _) => Some(color),
+            (Self::Ansi256, Color::Rgb { r, g, b }) => {
+                Some(Color::AnsiValue(nearest_256([r, g, b])))
+            }
+            (Self::Ansi16, Color::Rgb { r, g, b }) => Some(nearest_16([r, g, b])),
+            (Self::Ansi16, Color::AnsiValue(value)) => Some(nearest_16(ansi_256_to_rgb(value))),
+            _ => Some(color),
+        }
+    }
+}
+
+fn distance(a: [u8; 3], b: [u8; 3]) -> u32 {
+    a.iter()
+        .zip(b)
+        .map(|(&a, b)| (a as i32 - b as i32).pow(2) as u32)
+        .sum()
+}
+
+fn nearest_16(rgb: [u8; 3]) -> Color {
+    ANSI_16
+        .iter()
+        .min_by_key(|(_, value)| distance(rgb, *value))
+        .map(|(color, _)| *color)
+        .unwrap()
+}
+
+fn nearest_256(rgb: [u8; 3]) -> u8 {
+    let cube_idx = |v: u8| match v {
+        0..48 => 0,
+        48..115 => 1,
+        _ => (v - 35) / 40,
+    };
+    let [r, g, b] = rgb.map(cube_idx);
+    let cube = 16 + 36 * r + 6 * g + b;
+
+    let average = rgb.iter().map(|&v| v as u32).sum::<u32>() / 3;
+    let gray = 232 + ((average.saturating_sub(3) / 10).min(23) as u8);
+
+    if distance(rgb, ansi_256_to_rgb(gray)) < distance(rgb, ansi_256_to_rgb(cube)) {
+        gray
+    } else {
+        cube
+    }
+}
+
+fn ansi_256_to_rgb(value: u8) -> [u8; 3] {
+    match value {
+        0..16 => ANSI_16[value as usize].1,
+        16..232 => {
+            let idx = value - 16;
+            [idx / 36, idx / 6 % 6, idx % 6].map(|level| CUBE_LEVELS[level as usize])
+        }
+        _ => [8 + 10 * (value - 232); 3],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detect(choice: ColorChoice, vars: &[(&str, &str)]) -> ColorDepth {
+        ColorDepth::detect(choice, |name| {
+            vars.iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| value.to_string())
+        })
+    }
+
+    #[test]
+    fn test_detect() {
+        let auto = ColorChoice::Auto;
+
+        assert_eq!(detect(auto, &[("TERM", "xterm")]), ColorDepth::Ansi16);
+        assert_eq!(
+            detect(auto, &[("TERM", "xterm-256color")]),
+            ColorDepth::Ansi256
+        );
+        assert_eq!(
+            detect(
+                auto,
+                &[("TERM", "xterm-256color"), ("COLORTERM", "truecolor")]
+            ),
+            ColorDepth::TrueColor
+        );
+        assert_eq!(detect(auto, &[("TERM", "dumb")]), ColorDepth::None);
+        assert_eq!(
+            detect(auto, &[("TERM", "xterm-256color"), ("NO_COLOR", "1")]),
+            ColorDepth::None
+        );
+        assert_eq!(
+            detect(auto, &[("TERM", "xterm-256color"), ("NO_COLOR", "")]),
+            ColorDepth::Ansi256
+        );
+        assert_eq!(
+            detect(auto, &[("TERM", "dumb"), ("CLICOLOR_FORCE", "1")]),
+            ColorDepth::Ansi16
+        );
+        assert_eq!(
+            detect(auto, &[("TERM", "dumb"), ("CLICOLOR_FORCE", "0")]),
+            ColorDepth::None
+        );
+        assert_eq!(
+            detect(ColorChoice::Always, &[("TERM", "xterm"), ("NO_COLOR", "1")]),
+            ColorDepth::Ansi16
+        );
+        assert_eq!(
+            detect(ColorChoice::Never, &[("COLORTERM", "truecolor")]),
+            ColorDepth::None
+        );
+    }
+
+    #[test]
+    fn test_downgrade() {
+        let rgb = |r, g, b| Color::Rgb { r, g, b };
+
+        assert_eq!(
+            ColorDepth::TrueColor.downgrade(rgb(0x80, 0xae, 0xc3)),
+            Some(rgb(0x80, 0xae, 0xc3))
+        );
+        assert_eq!(
+            ColorDepth::Ansi256.downgrade(rgb(0xff, 0x00, 0x00)),
+            Some(Color::AnsiValue(196))
+        );
+        assert_eq!(
+            ColorDepth::Ansi256.downgrade(rgb(0x20, 0x20, 0x20)),
+            Some(Color::AnsiValue(234))
+        );
+        assert_eq!(
+            ColorDepth::Ansi256.downgrade(Color::DarkBlue),
+            Some(Color::DarkBlue)
+        );
+        assert_eq!(
+            ColorDepth::Ansi16.downgrade(rgb(0xf0, 0x10, 0x10)),
+            Some(Color::Red)
+        );
+        assert_eq!(
+            ColorDepth::Ansi16.downgrade(Color::AnsiValue(242)),
+            Some(Color::DarkGrey)
+        );
+        assert_eq!(
+            ColorDepth::Ansi16.downgrade(Color::AnsiValue(4)),
+            Some(Color::DarkBlue)
+        );
+        assert_eq!(ColorDepth::None.downgrade(Color::DarkBlue), None);
+    }
+}
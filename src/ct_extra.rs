@@ -1,18 +1,98 @@
-use std::io::{self, Write};
+use std::{
+    io::{self, Read, Write},
+    os::fd::AsFd,
+    time::{Duration, Instant},
+};
 
 use crossterm::{
     cursor, queue,
     terminal::{self, ClearType},
 };
+use rustix::event::{PollFd, PollFlags};
 
-pub fn queue_move_up_exact(file: &mut impl Write, lines: u16) -> io::Result<()> {
-    if lines > 0 {
-        queue!(file, cursor::MoveUp(lines))
-    } else {
-        Ok(())
-    }
-}
+/// How long to wait for the terminal to report the cursor position, same as crossterm.
+const CURSOR_POSITION_TIMEOUT: Duration = Duration::from_secs(2);
 
 pub fn queue_clear_and_reset_cursor(file: &mut impl Write) -> io::Result<()> {
     queue!(file, terminal::Clear(ClearType::All), cursor::MoveTo(0, 0))
 }
+
+fn parse_cursor_position(response: &[u8]) -> Option<(u16, u16)> {
+    // [ Cy ; Cx
+    let (row, col) = std::str::from_utf8(response.strip_prefix(b"[")?)
+        .ok()?
+        .split_once(';')?;
+
+    let row = row.parse::<u16>().ok()?.checked_sub(1)?;
+    let col = col.parse::<u16>().ok()?.checked_sub(1)?;
+    Some((col, row))
+}
+
+/// Waits until there's something to read from `tty`, for at most `timeout`. Returns whether
+/// there is.
+fn wait_readable(tty: &impl AsFd, timeout: Duration) -> io::Result<bool> {
+    let mut fds = [PollFd::new(tty, PollFlags::IN)];
+    let timeout = timeout.as_millis().try_into().unwrap_or(i32::MAX);
+
+    loop {
+        match rustix::event::poll(&mut fds, timeout) {
+            Ok(ready) => return Ok(ready > 0),
+            Err(rustix::io::Errno::INTR) => continue,
+            Err(err) => return Err(err.into()),
+        }
+    }
+}
+
+/// Returns the cursor position (column, row), querying the terminal through `tty`.
+///
+/// Unlike `crossterm::cursor::position`, this doesn't write the query to stdout, which is
+/// usually piped into something else. Raw mode must already be enabled. Fails if the
+/// terminal doesn't answer in time.
+pub fn cursor_position(tty: &mut (impl Read + Write + AsFd)) -> io::Result<(u16, u16)> {
+    tty.write_all(b"\x1B[6n")?;
+    tty.flush()?;
+
+    let deadline = Instant::now() + CURSOR_POSITION_TIMEOUT;
+    let mut response = Vec::new();
+    let mut byte = [0];
+
+    loop {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+
+        if !wait_readable(tty, timeout)? {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "the terminal did not report the cursor position",
+            ));
+        }
+
+        tty.read_exact(&mut byte)?;
+
+        match byte[0] {
+            b'\x1B' => response.clear(),
+            b'R' => break,
+            b => response.push(b),
+        }
+    }
+
+    parse_cursor_position(&response).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "could not parse the cursor position",
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cursor_position() {
+        assert_eq!(parse_cursor_position(b"[1;1"), Some((0, 0)));
+        assert_eq!(parse_cursor_position(b"[12;40"), Some((39, 11)));
+        assert_eq!(parse_cursor_position(b"[0;1"), None);
+        assert_eq!(parse_cursor_position(b"12;40"), None);
+        assert_eq!(parse_cursor_position(b"[12"), None);
+    }
+}
@@ -0,0 +1,137 @@
+use std::ops::Range;
+
+use crate::cli::FieldRange;
+
+/// Returns the byte ranges of each field in `line`. Without a delimiter, fields are
+/// separated by runs of whitespace, and leading or trailing whitespace is ignored.
+fn split_fields(line: &str, delimiter: Option<&str>) -> Vec<Range<usize>> {
+    match delimiter {
+        Some(delimiter) if !delimiter.is_empty() => {
+            let mut fields = Vec::new();
+            let mut start = 0;
+
+            for (idx, _) in line.match_indices(delimiter) {
+                fields.push(start..idx);
+                start = idx + delimiter.len();
+            }
+
+            fields.push(start..line.len());
+            fields
+        }
+        _ => {
+            let mut fields = Vec::new();
+            let mut start = None;
+
+            for (idx, ch) in line.char_indices() {
+                if ch.is_whitespace() {
+                    if let Some(start) = start.take() {
+                        fields.push(start..idx);
+                    }
+                } else if start.is_none() {
+                    start = Some(idx);
+                }
+            }
+
+            if let Some(start) = start {
+                fields.push(start..line.len());
+            }
+
+            fields
+        }
+    }
+}
+
+/// Converts `range` into an inclusive range of 0-based field indices, clamped to `len`
+/// fields. Returns `None` if the range is empty.
+fn resolve_range(range: &FieldRange, len: usize) -> Option<(usize, usize)> {
+    let len = len as isize;
+    let to_offset = |idx: isize| if idx > 0 { idx - 1 } else { len + idx };
+
+    let start = range.start.map_or(0, to_offset).max(0);
+    let end = range.end.map_or(len - 1, to_offset).min(len - 1);
+
+    (start <= end).then_some((start as usize, end as usize))
+}
+
+/// Extracts the fields within `ranges` from `line`. The text between fields of the same
+/// range is kept as-is, while separate ranges are joined by the delimiter (or a single space).
+/// Without any ranges, the whole line is returned.
+pub fn select_fields(line: &str, delimiter: Option<&str>, ranges: &[FieldRange]) -> String {
+    if ranges.is_empty() {
+        return line.to_string();
+    }
+
+    let fields = split_fields(line, delimiter);
+
+    ranges
+        .iter()
+        .filter_map(|range| resolve_range(range, fields.len()))
+        .map(|(first, last)| &line[fields[first].start..fields[last].end])
+        .collect::<Vec<_>>()
+        .join(delimiter.unwrap_or(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(start: Option<isize>, end: Option<isize>) -> FieldRange {
+        FieldRange { start, end }
+    }
+
+    #[test]
+    fn test_split_fields_whitespace() {
+        assert_eq!(split_fields("  aa b\tccc  ", None), vec![2..4, 5..6, 7..10]);
+        assert_eq!(split_fields("", None), vec![]);
+        assert_eq!(split_fields("   ", None), vec![]);
+    }
+
+    #[test]
+    fn test_split_fields_delimiter() {
+        assert_eq!(split_fields("aa\tb\t", Some("\t")), vec![0..2, 3..4, 5..5]);
+        assert_eq!(split_fields("a::b", Some("::")), vec![0..1, 3..4]);
+        assert_eq!(split_fields("", Some(",")), vec![0..0]);
+    }
+
+    #[test]
+    fn test_select_fields_single() {
+        let line = "42\tsome label\textra";
+        assert_eq!(
+            select_fields(line, Some("\t"), &[range(Some(1), Some(1))]),
+            "42"
+        );
+        assert_eq!(
+            select_fields(line, Some("\t"), &[range(Some(2), Some(2))]),
+            "some label"
+        );
+        assert_eq!(
+            select_fields(line, Some("\t"), &[range(Some(-1), Some(-1))]),
+            "extra"
+        );
+        assert_eq!(
+            select_fields(line, Some("\t"), &[range(Some(4), Some(4))]),
+            ""
+        );
+    }
+
+    #[test]
+    fn test_select_fields_ranges() {
+        let line = "a  b c   d";
+        assert_eq!(select_fields(line, None, &[]), line);
+        assert_eq!(
+            select_fields(line, None, &[range(Some(2), None)]),
+            "b c   d"
+        );
+        assert_eq!(select_fields(line, None, &[range(None, Some(-3))]), "a  b");
+        assert_eq!(
+            select_fields(
+                line,
+                None,
+                &[range(Some(1), Some(1)), range(Some(3), Some(4))]
+            ),
+            "a c   d"
+        );
+        assert_eq!(select_fields(line, None, &[range(Some(-9), Some(9))]), line);
+        assert_eq!(select_fields(line, None, &[range(Some(3), Some(2))]), "");
+    }
+}
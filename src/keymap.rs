@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+
+use crossterm::event::{KeyCode, KeyModifiers};
+
+use crate::cli::{Action, Key, KeyBinding};
+
+const DEFAULT_BINDINGS: [(KeyModifiers, KeyCode, Action); 19] = [
+    (KeyModifiers::NONE, KeyCode::Enter, Action::Accept),
+    (KeyModifiers::NONE, KeyCode::Esc, Action::Abort),
+    (KeyModifiers::CONTROL, KeyCode::Char('c'), Action::Abort),
+    (KeyModifiers::CONTROL, KeyCode::Char('n'), Action::Next),
+    (KeyModifiers::NONE, KeyCode::Tab, Action::Next),
+    (KeyModifiers::NONE, KeyCode::Down, Action::Down),
+    (KeyModifiers::CONTROL, KeyCode::Char('p'), Action::Prev),
+    (KeyModifiers::SHIFT, KeyCode::BackTab, Action::Prev),
+    (KeyModifiers::NONE, KeyCode::Up, Action::Up),
+    (KeyModifiers::NONE, KeyCode::Home, Action::First),
+    (KeyModifiers::NONE, KeyCode::End, Action::Last),
+    (KeyModifiers::NONE, KeyCode::PageDown, Action::PageDown),
+    (KeyModifiers::NONE, KeyCode::PageUp, Action::PageUp),
+    (KeyModifiers::NONE, KeyCode::Backspace, Action::DeleteChar),
+    (
+        KeyModifiers::CONTROL,
+        KeyCode::Char('h'),
+        Action::DeleteChar,
+    ),
+    (
+        KeyModifiers::CONTROL,
+        KeyCode::Char('w'),
+        Action::DeleteWord,
+    ),
+    (
+        KeyModifiers::CONTROL,
+        KeyCode::Char('u'),
+        Action::ClearQuery,
+    ),
+    (KeyModifiers::CONTROL, KeyCode::Char(' '), Action::Toggle),
+    (KeyModifiers::CONTROL, KeyCode::Char('r'), Action::Reload),
+];
+
+/// Maps keys to the actions they're bound to.
+#[derive(Debug, Clone)]
+pub struct Keymap(HashMap<Key, Action>);
+
+impl Keymap {
+    /// Builds a keymap out of the default bindings, overridden by `bindings`.
+    pub fn new(bindings: &[KeyBinding]) -> Self {
+        let defaults = DEFAULT_BINDINGS
+            .into_iter()
+            .map(|(modifiers, code, action)| (Key { modifiers, code }, action));
+        let overrides = bindings
+            .iter()
+            .map(|binding| (normalize(binding.key), binding.action));
+
+        Self(defaults.chain(overrides).collect())
+    }
+
+    pub fn get(&self, key: Key) -> Option<Action> {
+        self.0.get(&normalize(key)).copied()
+    }
+}
+
+/// Drops the shift modifier from characters, since it's already reflected in the character
+/// itself (e.g. 'A'), and terminals don't always report it.
+pub fn normalize(key: Key) -> Key {
+    match key.code {
+        KeyCode::Char(_) => Key {
+            modifiers: key.modifiers - KeyModifiers::SHIFT,
+            ..key
+        },
+        _ => key,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(modifiers: KeyModifiers, code: KeyCode) -> Key {
+        Key { modifiers, code }
+    }
+
+    #[test]
+    fn test_keymap_overrides() {
+        let keymap = Keymap::new(&[
+            KeyBinding {
+                key: key(KeyModifiers::CONTROL, KeyCode::Char('j')),
+                action: Action::Next,
+            },
+            KeyBinding {
+                key: key(KeyModifiers::NONE, KeyCode::Tab),
+                action: Action::Toggle,
+            },
+        ]);
+
+        assert_eq!(
+            keymap.get(key(KeyModifiers::CONTROL, KeyCode::Char('j'))),
+            Some(Action::Next)
+        );
+        assert_eq!(
+            keymap.get(key(KeyModifiers::NONE, KeyCode::Tab)),
+            Some(Action::Toggle)
+        );
+        assert_eq!(
+            keymap.get(key(KeyModifiers::CONTROL, KeyCode::Char('n'))),
+            Some(Action::Next)
+        );
+        assert_eq!(
+            keymap.get(key(KeyModifiers::NONE, KeyCode::Char('j'))),
+            None
+        );
+    }
+
+    #[test]
+    fn test_keymap_shifted_chars() {
+        let keymap = Keymap::new(&[KeyBinding {
+            key: key(KeyModifiers::NONE, KeyCode::Char('J')),
+            action: Action::Next,
+        }]);
+
+        assert_eq!(
+            keymap.get(key(KeyModifiers::SHIFT, KeyCode::Char('J'))),
+            Some(Action::Next)
+        );
+        assert_eq!(
+            keymap.get(key(KeyModifiers::SHIFT, KeyCode::BackTab)),
+            Some(Action::Prev)
+        );
+    }
+}
@@ -1,22 +1,29 @@
 use std::{
     fs::OpenOptions,
-    io::{self, Read},
+    io,
+    os::fd::AsFd,
     panic::{self, PanicHookInfo},
 };
 
 use app::App;
-use clap::Parser;
 use cli::{Args, CliError};
 
 mod app;
 mod cli;
+mod color;
 mod ct_extra;
+mod fields;
+mod keymap;
+mod matcher;
 mod menu;
 mod numeric;
+mod source;
 mod string;
+mod theme;
+mod trie;
 
-fn get_tty() -> io::Result<impl io::Write> {
-    OpenOptions::new().write(true).open("/dev/tty")
+fn get_tty() -> io::Result<impl io::Read + io::Write + AsFd> {
+    OpenOptions::new().read(true).write(true).open("/dev/tty")
 }
 
 fn add_panic_hook(hook: Box<dyn Fn(&PanicHookInfo<'_>) + 'static + Sync + Send>) {
@@ -28,46 +35,46 @@ fn add_panic_hook(hook: Box<dyn Fn(&PanicHookInfo<'_>) + 'static + Sync + Send>)
 }
 
 fn try_main(args: Args) -> Result<(), CliError> {
-    let mut buf = String::new();
-    io::stdin().lock().read_to_string(&mut buf)?;
-    let lines: Vec<_> = buf.lines().map(String::from).collect();
-
-    if lines.is_empty() {
-        return Err(CliError::NoInput);
-    }
+    let source = source::spawn_input_reader(&args)?;
+    let mut app = App::new(args, source);
+    app.wait_for_input()?;
 
     let mut tty = get_tty()?;
 
-    if args.clear {
+    if app.args.clear {
         let _ = ct_extra::queue_clear_and_reset_cursor(&mut tty);
     }
 
-    let mut app = App::new(args, &lines);
-    App::init(&mut tty)?;
+    let fullscreen = app.args.fullscreen;
+    App::init(&mut tty, fullscreen)?;
 
-    add_panic_hook(Box::new(|_| {
-        let _ = get_tty().and_then(|mut tty| App::deinit(&mut tty));
+    add_panic_hook(Box::new(move |_| {
+        let _ = get_tty().and_then(|mut tty| App::deinit(&mut tty, fullscreen));
     }));
 
     let result = app.run(&mut tty);
-    App::deinit(&mut tty)?;
+    App::deinit(&mut tty, fullscreen)?;
+
+    let terminator = if app.args.print0 { '\0' } else { '\n' };
+
+    for body in result? {
+        print!("{body}{terminator}");
+    }
 
-    result
-        .map_err(CliError::from)
-        .and_then(|selection| selection.ok_or(CliError::Interrupted))
-        .map(|selection| {
-            println!("{selection}");
-        })
+    Ok(())
 }
 
 fn main() {
-    let args @ Args { silent, .. } = Args::parse();
+    let args @ Args { silent, .. } = cli::parse_args().unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(err.exit_code());
+    });
     let main_result = try_main(args);
 
     if let Err(err) = main_result {
         if !err.is_interrupted() && !silent {
             eprintln!("{}", err);
         }
-        std::process::exit(130);
+        std::process::exit(err.exit_code());
     }
 }
@@ -0,0 +1,263 @@
+use std::{iter, ops::Range};
+
+use crate::cli::MatchMode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaseMatching {
+    Respect,
+    Ignore,
+    /// Ignore case unless the input has uppercase characters.
+    Smart,
+}
+
+impl CaseMatching {
+    pub fn from_flags(ignore_case: bool, smart_case: bool) -> Self {
+        if smart_case {
+            Self::Smart
+        } else if ignore_case {
+            Self::Ignore
+        } else {
+            Self::Respect
+        }
+    }
+
+    pub fn ignores_case(self, input: &str) -> bool {
+        match self {
+            Self::Respect => false,
+            Self::Ignore => true,
+            Self::Smart => !input.chars().any(char::is_uppercase),
+        }
+    }
+}
+
+/// Lowercases `text` one char at a time, so that it can be compared without regard to case.
+pub fn fold_case(text: &str) -> String {
+    text.chars().flat_map(char::to_lowercase).collect()
+}
+
+/// Like `fold_case`, but also returns the byte range of `text` each byte of the result
+/// comes from.
+fn fold_case_with_origins(text: &str) -> (String, Vec<Range<usize>>) {
+    let mut folded = String::with_capacity(text.len());
+    let mut origins = Vec::with_capacity(text.len());
+
+    for (idx, ch) in text.char_indices() {
+        let folded_len = folded.len();
+        folded.extend(ch.to_lowercase());
+        origins.extend(iter::repeat_n(
+            idx..idx + ch.len_utf8(),
+            folded.len() - folded_len,
+        ));
+    }
+
+    (folded, origins)
+}
+
+fn is_word_start(text: &str, idx: usize) -> bool {
+    text[..idx]
+        .chars()
+        .next_back()
+        .is_none_or(|ch| !ch.is_alphanumeric())
+}
+
+fn find_fuzzy_match(text: &str, input: &str) -> Option<Vec<Range<usize>>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    let mut text_chars = text.char_indices();
+
+    for input_ch in input.chars() {
+        let (idx, ch) = text_chars.find(|&(_, ch)| ch == input_ch)?;
+        let end = idx + ch.len_utf8();
+
+        match ranges.last_mut() {
+            Some(last) if last.end == idx => last.end = end,
+            _ => ranges.push(idx..end),
+        }
+    }
+
+    Some(ranges)
+}
+
+/// Returns the byte ranges of `text` matched by `input`, or `None` if it doesn't match.
+fn find_match(text: &str, input: &str, mode: MatchMode) -> Option<Vec<Range<usize>>> {
+    let matched_at = |start: usize| {
+        let range = start..start + input.len();
+        vec![range]
+    };
+
+    match mode {
+        MatchMode::Prefix | MatchMode::Hint => text.starts_with(input).then(|| matched_at(0)),
+        MatchMode::Substring => text.find(input).map(matched_at),
+        MatchMode::WordPrefix => text
+            .char_indices()
+            .map(|(idx, _)| idx)
+            .chain([text.len()])
+            .find(|&idx| is_word_start(text, idx) && text[idx..].starts_with(input))
+            .map(matched_at),
+        MatchMode::Fuzzy => find_fuzzy_match(text, input),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Matcher {
+    pub mode: MatchMode,
+    pub case: CaseMatching,
+}
+
+impl Matcher {
+    /// Returns a matcher with the same case matching, but which matches by prefix.
+    pub fn by_prefix(self) -> Self {
+        Self {
+            mode: MatchMode::Prefix,
+            ..self
+        }
+    }
+
+    /// Returns the byte ranges of `text` matched by `input`, or `None` if it doesn't match.
+    pub fn find_match(&self, text: &str, input: &str) -> Option<Vec<Range<usize>>> {
+        if !self.case.ignores_case(input) {
+            return find_match(text, input, self.mode);
+        }
+
+        let (folded_text, origins) = fold_case_with_origins(text);
+        let ranges = find_match(&folded_text, &fold_case(input), self.mode)?;
+
+        // Map the ranges back into the original text.
+        let ranges = ranges
+            .into_iter()
+            .map(|range| {
+                if range.is_empty() {
+                    let idx = origins
+                        .get(range.start)
+                        .map_or(text.len(), |origin| origin.start);
+                    idx..idx
+                } else {
+                    origins[range.start].start..origins[range.end - 1].end
+                }
+            })
+            .collect();
+
+        Some(ranges)
+    }
+
+    pub fn is_exact_match(&self, text: &str, input: &str) -> bool {
+        if self.case.ignores_case(input) {
+            fold_case(text) == fold_case(input)
+        } else {
+            text == input
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::single_range_in_vec_init)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_match_prefix() {
+        assert_eq!(
+            find_match("foobar", "foo", MatchMode::Prefix),
+            Some(vec![0..3])
+        );
+        assert_eq!(
+            find_match("foobar", "", MatchMode::Prefix),
+            Some(vec![0..0])
+        );
+        assert_eq!(find_match("foobar", "bar", MatchMode::Prefix), None);
+    }
+
+    #[test]
+    fn test_find_match_substring() {
+        assert_eq!(
+            find_match("feature/bar", "bar", MatchMode::Substring),
+            Some(vec![8..11])
+        );
+        assert_eq!(
+            find_match("ñandú", "dú", MatchMode::Substring),
+            Some(vec![4..7])
+        );
+        assert_eq!(find_match("feature/bar", "baz", MatchMode::Substring), None);
+    }
+
+    #[test]
+    fn test_find_match_word_prefix() {
+        assert_eq!(
+            find_match("feature/bar", "ba", MatchMode::WordPrefix),
+            Some(vec![8..10])
+        );
+        assert_eq!(
+            find_match("foo bar", "foo", MatchMode::WordPrefix),
+            Some(vec![0..3])
+        );
+        assert_eq!(
+            find_match("abar bar", "bar", MatchMode::WordPrefix),
+            Some(vec![5..8])
+        );
+        assert_eq!(find_match("foobar", "bar", MatchMode::WordPrefix), None);
+    }
+
+    #[test]
+    fn test_find_match_fuzzy() {
+        assert_eq!(
+            find_match("feature/bar", "ftbr", MatchMode::Fuzzy),
+            Some(vec![0..1, 3..4, 8..9, 10..11])
+        );
+        assert_eq!(
+            find_match("feature/bar", "feab", MatchMode::Fuzzy),
+            Some(vec![0..3, 8..9])
+        );
+        assert_eq!(
+            find_match("feature/bar", "", MatchMode::Fuzzy),
+            Some(vec![])
+        );
+        assert_eq!(find_match("feature/bar", "rf", MatchMode::Fuzzy), None);
+    }
+
+    #[test]
+    fn test_case_matching_smart() {
+        assert!(CaseMatching::Smart.ignores_case("docs"));
+        assert!(!CaseMatching::Smart.ignores_case("Docs"));
+        assert!(CaseMatching::Ignore.ignores_case("Docs"));
+        assert!(!CaseMatching::Respect.ignores_case("docs"));
+    }
+
+    #[test]
+    fn test_matcher_ignore_case() {
+        let matcher = Matcher {
+            mode: MatchMode::Substring,
+            case: CaseMatching::Ignore,
+        };
+
+        assert_eq!(matcher.find_match("My Docs", "docs"), Some(vec![3..7]));
+        assert_eq!(matcher.find_match("ÁRBOL", "árb"), Some(vec![0..4]));
+        assert_eq!(matcher.find_match("My Docs", "x"), None);
+        assert!(matcher.is_exact_match("Docs", "dOCS"));
+    }
+
+    #[test]
+    fn test_matcher_smart_case() {
+        let matcher = Matcher {
+            mode: MatchMode::Prefix,
+            case: CaseMatching::Smart,
+        };
+
+        assert_eq!(matcher.find_match("Docs", "do"), Some(vec![0..2]));
+        assert_eq!(matcher.find_match("Docs", "Do"), Some(vec![0..2]));
+        assert_eq!(matcher.find_match("docs", "Do"), None);
+        assert!(!matcher.is_exact_match("docs", "Docs"));
+    }
+
+    #[test]
+    fn test_matcher_fuzzy_ignore_case() {
+        let matcher = Matcher {
+            mode: MatchMode::Fuzzy,
+            case: CaseMatching::Ignore,
+        };
+
+        // The Kelvin sign takes up 3 bytes, but folds into a 1-byte 'k'.
+        assert_eq!(
+            matcher.find_match("\u{212A}ey Box", "kb"),
+            Some(vec![0..3, 6..7])
+        );
+    }
+}
@@ -1,82 +1,193 @@
-use std::collections::HashMap;
-
-use crate::numeric;
+use std::{
+    collections::{BTreeMap, HashSet},
+    ops::Range,
+};
+
+use crate::{
+    cli::MatchMode,
+    matcher::{self, CaseMatching, Matcher},
+    numeric,
+    trie::PrefixTrie,
+};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Entry {
     pub body: String,
+    /// Key declared explicitly in the input, matched instead of the body.
+    pub key: Option<String>,
+    /// Length of the prefix in bytes. Always lies on a grapheme cluster boundary of the
+    /// entry's target.
     pub prefix_len: usize,
     pub auto_accept: bool,
 }
 
 impl Entry {
+    /// Returns the text the input is matched against.
+    pub fn target(&self) -> &str {
+        self.key.as_deref().unwrap_or(&self.body)
+    }
+
     pub fn prefix(&self) -> &str {
-        &self.body[..self.prefix_len]
+        &self.target()[..self.prefix_len]
+    }
+
+    /// Whether the entry is matched by prefix, which is always the case for entries with an
+    /// explicit key.
+    pub fn matches_by_prefix(&self, mode: MatchMode) -> bool {
+        self.key.is_some() || matches!(mode, MatchMode::Prefix | MatchMode::Hint)
     }
 
-    pub fn is_selectable(&self, input: &str) -> bool {
-        self.body.starts_with(input)
+    /// Returns the byte ranges of the target matched by `input`, if it matches at all.
+    pub fn find_match(&self, input: &str, matcher: Matcher) -> Option<Vec<Range<usize>>> {
+        let matcher = if self.matches_by_prefix(matcher.mode) {
+            matcher.by_prefix()
+        } else {
+            matcher
+        };
+
+        matcher.find_match(self.target(), input)
     }
 
-    pub fn is_acceptable(&self, input: &str) -> bool {
-        self.auto_accept && input == self.prefix()
+    pub fn is_selectable(&self, input: &str, matcher: Matcher) -> bool {
+        self.find_match(input, matcher).is_some()
+    }
+
+    pub fn is_acceptable(&self, input: &str, matcher: Matcher) -> bool {
+        self.auto_accept && matcher.is_exact_match(self.prefix(), input)
     }
 }
 
-fn generate_entries(lines: &[String]) -> Vec<Entry> {
-    let mut char_iters: Vec<_> = lines.iter().map(|ln| ln.chars()).collect();
-    let mut groups: Vec<(usize, Vec<usize>)> = vec![(0, (0..lines.len()).collect())];
-    let mut entries = HashMap::<usize, Entry>::new();
+/// Splits a key declared as "[key] body", or as "key<delimiter>body" if a delimiter is
+/// given, off of `line`.
+pub fn split_explicit_key(line: &str, delimiter: Option<&str>) -> (Option<String>, String) {
+    let split = match delimiter {
+        Some(delimiter) => line.split_once(delimiter),
+        None => line
+            .strip_prefix('[')
+            .and_then(|rest| rest.split_once(']'))
+            .map(|(key, body)| (key, body.trim_start())),
+    };
+
+    match split {
+        Some((key, body)) if !key.is_empty() => (Some(key.to_string()), body.to_string()),
+        _ => (None, line.to_string()),
+    }
+}
 
-    while let Some((prefix_len, indices)) = groups.pop() {
-        if let [idx] = indices[..] {
-            // Just one line with this prefix, it's over.
-            entries.insert(
-                idx,
-                Entry {
-                    body: lines[idx].clone(),
-                    prefix_len,
-                    auto_accept: true,
-                },
-            );
+/// Declared keys seen so far, none of which is a prefix of another, since that would make
+/// the longer one impossible to type. Keys are compared case-insensitively if `fold_case`
+/// is set.
+#[derive(Debug, Clone, Default)]
+pub struct KeySet {
+    fold_case: bool,
+    /// Maps each key, as compared, to the key as it was declared.
+    keys: BTreeMap<String, String>,
+}
+
+impl KeySet {
+    pub fn new(fold_case: bool) -> Self {
+        Self {
+            fold_case,
+            keys: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the keys as they are compared, i.e. case-folded if the set folds case.
+    pub fn compared_keys(&self) -> impl Iterator<Item = &str> {
+        self.keys.keys().map(String::as_str)
+    }
+
+    /// Adds `key` to the set, unless it conflicts with a key already in it, in which case
+    /// that key is returned instead.
+    pub fn insert(&mut self, key: &str) -> Result<(), &str> {
+        let compared = if self.fold_case {
+            matcher::fold_case(key)
         } else {
-            // More than one line with the prefix.
-            let mut lines_by_next_char = HashMap::<char, Vec<usize>>::new();
-
-            for idx in indices {
-                // This will always be the next character after the current prefix,
-                // since chars are consumed precisely one-by-one as they are required.
-                // That is:
-                // charss[idx].next() = lines[idx].chars().nth(prefix.len())
-                match char_iters[idx].next() {
-                    None => {
-                        // This line is a prefix of some other line, so it can't be
-                        // auto-accepted.
-                        entries.insert(
-                            idx,
-                            Entry {
-                                body: lines[idx].clone(),
-                                prefix_len,
-                                auto_accept: false,
-                            },
-                        );
-                        continue;
-                    }
-                    Some(next_ch) => {
-                        lines_by_next_char.entry(next_ch).or_default().push(idx);
-                    }
-                }
-            }
+            key.to_string()
+        };
+
+        // Keys that start with this one sort right after it, and keys this one starts with
+        // can only be its prefixes.
+        let conflict = self
+            .keys
+            .range(compared.clone()..)
+            .next()
+            .filter(|(other, _)| other.starts_with(&compared))
+            .map(|(other, _)| other.clone())
+            .or_else(|| {
+                compared
+                    .char_indices()
+                    .map(|(idx, _)| &compared[..idx])
+                    .find(|prefix| self.keys.contains_key(*prefix))
+                    .map(str::to_string)
+            });
 
-            for group_indices in lines_by_next_char.into_values() {
-                groups.push((prefix_len + 1, group_indices))
+        match conflict {
+            Some(other) => Err(&self.keys[&other]),
+            None => {
+                self.keys.insert(compared, key.to_string());
+                Ok(())
             }
         }
     }
+}
+
+fn nth_hint(mut n: usize, len: usize, alphabet: &[char]) -> String {
+    let mut hint = vec![alphabet[0]; len];
 
-    (0..lines.len())
-        .map(|i| entries.remove(&i).unwrap())
-        .collect()
+    for ch in hint.iter_mut().rev() {
+        *ch = alphabet[n % alphabet.len()];
+        n /= alphabet.len();
+    }
+
+    hint.into_iter().collect()
+}
+
+/// Generates up to `count` hints of the same length out of `alphabet`, skipping any that
+/// would clash with `taken_keys`. Fewer hints are returned only if the taken keys leave no
+/// room for more of them.
+pub fn generate_hints(count: usize, alphabet: &[char], taken_keys: &[&str]) -> Vec<String> {
+    if count == 0 || alphabet.len() < 2 {
+        return Vec::new();
+    }
+
+    let taken_keys_set: HashSet<_> = taken_keys.iter().copied().collect();
+    let max_key_len = taken_keys
+        .iter()
+        .map(|key| key.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    let mut len = 1;
+
+    loop {
+        let taken_prefixes: HashSet<String> = taken_keys
+            .iter()
+            .map(|key| key.chars().take(len).collect())
+            .collect();
+
+        let clashes = |hint: &String| {
+            taken_prefixes.contains(hint)
+                || hint
+                    .char_indices()
+                    .any(|(i, ch)| taken_keys_set.contains(&hint[..i + ch.len_utf8()]))
+        };
+
+        let total = alphabet.len().checked_pow(len as u32).unwrap_or(usize::MAX);
+        let hints: Vec<_> = (0..total)
+            .map(|n| nth_hint(n, len, alphabet))
+            .filter(|hint| !clashes(hint))
+            .take(count)
+            .collect();
+
+        // Past the longest taken key, every extra character multiplies the amount of
+        // available hints, so if there are none by then there never will be.
+        if hints.len() == count || (hints.is_empty() && len >= max_key_len) {
+            break hints;
+        }
+
+        len += 1;
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -85,21 +196,101 @@ pub enum SearchDirection {
     Backwards,
 }
 
+impl SearchDirection {
+    pub fn reverse(self) -> Self {
+        match self {
+            Self::Forwards => Self::Backwards,
+            Self::Backwards => Self::Forwards,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Menu {
     entries: Vec<Entry>,
+    /// Holds the target of every entry, by index.
+    trie: PrefixTrie,
+    matcher: Matcher,
     selection: Option<usize>,
+    marked: Vec<bool>,
 }
 
 impl Menu {
-    pub fn from_lines(lines: &[String]) -> Self {
-        assert!(!lines.is_empty(), "lines must have at least one element");
-
-        let entries = generate_entries(lines);
+    pub fn new(matcher: Matcher) -> Self {
         Self {
-            entries,
-            selection: Some(0),
+            entries: Vec::new(),
+            trie: PrefixTrie::new(matcher.case != CaseMatching::Respect),
+            matcher,
+            selection: None,
+            marked: Vec::new(),
+        }
+    }
+
+    /// Builds a menu out of `lines`, where `keys` holds the explicit key of each line, if
+    /// any. Declared keys shouldn't conflict with each other (see `KeySet`).
+    #[cfg(test)]
+    pub fn from_lines(lines: &[String], keys: &[Option<String>], matcher: Matcher) -> Self {
+        assert_eq!(lines.len(), keys.len(), "every line must have a key slot");
+
+        let mut menu = Self::new(matcher);
+
+        for (line, key) in lines.iter().zip(keys) {
+            menu.push(line.clone(), key.clone());
         }
+
+        menu.selection = if lines.is_empty() { None } else { Some(0) };
+        menu
+    }
+
+    /// Appends a line to the menu. `key` shouldn't conflict with the declared keys of
+    /// existing entries (see `KeySet`).
+    pub fn push(&mut self, line: String, key: Option<String>) {
+        self.entries.push(Entry {
+            body: line,
+            key,
+            prefix_len: 0,
+            auto_accept: true,
+        });
+        self.marked.push(false);
+        self.insert_target(self.entries.len() - 1);
+    }
+
+    /// Replaces the explicit key of the entry at `idx`. The same rules as for `push` apply.
+    pub fn set_key(&mut self, idx: usize, key: Option<String>) {
+        let affected = self.trie.remove(self.entries[idx].target(), idx);
+        self.entries[idx].key = key;
+
+        if let Some(affected) = affected {
+            self.update_prefix(affected);
+        }
+
+        self.insert_target(idx);
+    }
+
+    fn insert_target(&mut self, idx: usize) {
+        if let Some(affected) = self.trie.insert(self.entries[idx].target(), idx) {
+            self.update_prefix(affected);
+        }
+
+        self.update_prefix(idx);
+    }
+
+    fn update_prefix(&mut self, idx: usize) {
+        let entry = &mut self.entries[idx];
+
+        (entry.prefix_len, entry.auto_accept) = match &entry.key {
+            // Declared keys are used whole, but like any other text, they can't be
+            // auto-accepted if other texts start with them, or those would be impossible to
+            // type.
+            Some(key) => (key.len(), self.trie.unique_prefix_len(key).1),
+            None => self.trie.unique_prefix_len(&entry.body),
+        };
+    }
+
+    /// Whether the trie alone can tell which entries `input` matches.
+    fn trie_matches(&self, input: &str) -> bool {
+        matches!(self.matcher.mode, MatchMode::Prefix | MatchMode::Hint)
+            && self.matcher.case.ignores_case(input) == self.trie.fold_case()
     }
 
     pub fn len(&self) -> usize {
@@ -114,16 +305,78 @@ impl Menu {
         self.selection.as_ref()
     }
 
+    pub fn matcher(&self) -> Matcher {
+        self.matcher
+    }
+
     pub fn has_selectable(&self, input: &str) -> bool {
-        self.entries.iter().any(|entry| entry.is_selectable(input))
+        if self.trie_matches(input) {
+            self.trie.has_prefixed(input)
+        } else {
+            self.entries
+                .iter()
+                .any(|entry| entry.is_selectable(input, self.matcher))
+        }
     }
 
-    pub fn find_acceptable(&self, input: &str) -> Option<&Entry> {
-        self.entries.iter().find(|entry| entry.is_acceptable(input))
+    pub fn find_acceptable(&self, input: &str) -> Option<usize> {
+        // An entry can only be accepted once the input reaches the end of its prefix,
+        // which takes it to the entry's own branch of the trie. Case may still need to be
+        // checked, since the trie folds case even when smart case doesn't.
+        let by_prefix = self
+            .trie
+            .prefix_candidates(input)
+            .into_iter()
+            .filter(|&idx| self.entries[idx].is_acceptable(input, self.matcher))
+            .min();
+
+        match self.matcher.mode {
+            MatchMode::Prefix | MatchMode::Hint => by_prefix,
+            // Prefixes mean nothing in other modes (except for explicit keys), so just wait
+            // until there's only one entry left.
+            MatchMode::Substring | MatchMode::Fuzzy | MatchMode::WordPrefix => by_prefix
+                .filter(|&idx| self.entries[idx].key.is_some())
+                .or_else(|| {
+                    let mut selectable = (0..self.entries.len())
+                        .filter(|&idx| self.entries[idx].is_selectable(input, self.matcher));
+
+                    match (selectable.next(), selectable.next()) {
+                        (Some(idx), None) => Some(idx),
+                        _ => None,
+                    }
+                }),
+        }
+    }
+
+    pub fn manual_accept(&self) -> Option<usize> {
+        self.selection
+    }
+
+    pub fn is_marked(&self, idx: usize) -> bool {
+        self.marked[idx]
+    }
+
+    pub fn toggle_mark(&mut self, idx: usize) {
+        self.marked[idx] = !self.marked[idx];
     }
 
-    pub fn manual_accept(&self) -> Option<&Entry> {
-        self.selection.map(|sel| &self.entries[sel])
+    /// Returns the indices of the marked entries, in input order.
+    pub fn marked(&self) -> impl Iterator<Item = usize> + '_ {
+        self.marked
+            .iter()
+            .enumerate()
+            .filter(|(_, &marked)| marked)
+            .map(|(idx, _)| idx)
+    }
+
+    /// Selects the entry at `idx`, as long as it's selectable. Returns whether it is.
+    pub fn select(&mut self, idx: usize, input: &str) -> bool {
+        if self.entries[idx].is_selectable(input, self.matcher) {
+            self.selection = Some(idx);
+            true
+        } else {
+            false
+        }
     }
 
     pub fn update_selection(&mut self, new_input: &str) -> bool {
@@ -132,13 +385,13 @@ impl Menu {
         let search_result = self.entries[start..]
             .iter()
             .enumerate()
-            .find(|(_, entry)| entry.is_selectable(new_input))
+            .find(|(_, entry)| entry.is_selectable(new_input, self.matcher))
             .map(|(i, _)| start + i)
             .or_else(|| {
                 self.entries[..start]
                     .iter()
                     .enumerate()
-                    .rfind(|(_, entry)| entry.is_selectable(new_input))
+                    .rfind(|(_, entry)| entry.is_selectable(new_input, self.matcher))
                     .map(|(i, _)| i)
             });
 
@@ -150,7 +403,30 @@ impl Menu {
         }
     }
 
+    /// Selects the first selectable entry, or the last one if searching backwards. Returns
+    /// whether the selection changed.
+    pub fn select_end(&mut self, input: &str, direction: SearchDirection) -> bool {
+        let mut selectable = (0..self.entries.len())
+            .filter(|&idx| self.entries[idx].is_selectable(input, self.matcher));
+
+        let found = match direction {
+            SearchDirection::Forwards => selectable.next(),
+            SearchDirection::Backwards => selectable.next_back(),
+        };
+
+        if found.is_some() && self.selection != found {
+            self.selection = found;
+            true
+        } else {
+            false
+        }
+    }
+
     pub fn move_selection(&mut self, input: &str, direction: SearchDirection, wrap: bool) -> bool {
+        if self.entries.is_empty() {
+            return false;
+        }
+
         let mut candidate = self.selection.unwrap_or(0);
         let mut did_wrap;
 
@@ -164,7 +440,7 @@ impl Menu {
                 break false;
             }
 
-            if self.entries[candidate].is_selectable(input) {
+            if self.entries[candidate].is_selectable(input, self.matcher) {
                 self.selection = Some(candidate);
                 break true;
             }
@@ -176,29 +452,60 @@ impl Menu {
 mod tests {
     use super::*;
 
+    const PREFIX_MATCHER: Matcher = Matcher {
+        mode: MatchMode::Prefix,
+        case: CaseMatching::Respect,
+    };
+
+    /// Returns the entries generated for `lines` when they sit next to entries declaring
+    /// `taken_keys`.
+    fn generate_entries(lines: &[String], taken_keys: &[&str], fold_case: bool) -> Vec<Entry> {
+        let matcher = Matcher {
+            case: if fold_case {
+                CaseMatching::Ignore
+            } else {
+                CaseMatching::Respect
+            },
+            ..PREFIX_MATCHER
+        };
+        let mut menu = Menu::new(matcher);
+
+        for line in lines {
+            menu.push(line.clone(), None);
+        }
+        for key in taken_keys {
+            menu.push(String::new(), Some(key.to_string()));
+        }
+
+        menu.entries[..lines.len()].to_vec()
+    }
+
     #[test]
     fn test_generate_entries_empty() {
-        assert_eq!(generate_entries(&[]), vec![]);
+        assert_eq!(generate_entries(&[], &[], false), vec![]);
     }
 
     #[test]
     fn test_generate_entries_simple() {
         let lines = ["aaa", "bbb", "ccc"].map(str::to_string);
         assert_eq!(
-            generate_entries(&lines),
+            generate_entries(&lines, &[], false),
             vec![
                 Entry {
                     body: "aaa".to_string(),
+                    key: None,
                     prefix_len: 1,
                     auto_accept: true
                 },
                 Entry {
                     body: "bbb".to_string(),
+                    key: None,
                     prefix_len: 1,
                     auto_accept: true
                 },
                 Entry {
                     body: "ccc".to_string(),
+                    key: None,
                     prefix_len: 1,
                     auto_accept: true
                 },
@@ -210,30 +517,35 @@ mod tests {
     fn test_generate_entries_complex() {
         let lines = ["abcddd", "abce", "abb", "cc", "ccd"].map(str::to_string);
         assert_eq!(
-            generate_entries(&lines),
+            generate_entries(&lines, &[], false),
             vec![
                 Entry {
                     body: "abcddd".to_string(),
+                    key: None,
                     prefix_len: 4,
                     auto_accept: true
                 },
                 Entry {
                     body: "abce".to_string(),
+                    key: None,
                     prefix_len: 4,
                     auto_accept: true
                 },
                 Entry {
                     body: "abb".to_string(),
+                    key: None,
                     prefix_len: 3,
                     auto_accept: true
                 },
                 Entry {
                     body: "cc".to_string(),
+                    key: None,
                     prefix_len: 2,
                     auto_accept: false
                 },
                 Entry {
                     body: "ccd".to_string(),
+                    key: None,
                     prefix_len: 3,
                     auto_accept: true
                 },
@@ -245,34 +557,400 @@ mod tests {
     fn test_generate_entries_repeated() {
         let lines = ["aaa", "aaa", "bbb", "cc", "cc"].map(str::to_string);
         assert_eq!(
-            generate_entries(&lines),
+            generate_entries(&lines, &[], false),
             vec![
                 Entry {
                     body: "aaa".to_string(),
+                    key: None,
                     prefix_len: 3,
                     auto_accept: false
                 },
                 Entry {
                     body: "aaa".to_string(),
+                    key: None,
                     prefix_len: 3,
                     auto_accept: false
                 },
                 Entry {
                     body: "bbb".to_string(),
+                    key: None,
                     prefix_len: 1,
                     auto_accept: true
                 },
                 Entry {
                     body: "cc".to_string(),
+                    key: None,
                     prefix_len: 2,
                     auto_accept: false
                 },
                 Entry {
                     body: "cc".to_string(),
+                    key: None,
                     prefix_len: 2,
                     auto_accept: false
                 },
             ]
         );
     }
+
+    #[test]
+    fn test_generate_entries_non_ascii() {
+        let lines = ["árbol", "ábaco", "água", "ñu"].map(str::to_string);
+        let entries = generate_entries(&lines, &[], false);
+
+        assert_eq!(
+            entries,
+            vec![
+                Entry {
+                    body: "árbol".to_string(),
+                    key: None,
+                    prefix_len: "ár".len(),
+                    auto_accept: true
+                },
+                Entry {
+                    body: "ábaco".to_string(),
+                    key: None,
+                    prefix_len: "áb".len(),
+                    auto_accept: true
+                },
+                Entry {
+                    body: "água".to_string(),
+                    key: None,
+                    prefix_len: "ág".len(),
+                    auto_accept: true
+                },
+                Entry {
+                    body: "ñu".to_string(),
+                    key: None,
+                    prefix_len: "ñ".len(),
+                    auto_accept: true
+                },
+            ]
+        );
+        assert_eq!(entries[0].prefix(), "ár");
+        assert_eq!(entries[2].prefix(), "ág");
+    }
+
+    #[test]
+    fn test_generate_entries_combining_marks() {
+        // "e\u{301}" renders as "é", but is made up of two chars.
+        let lines = ["e\u{301}te\u{301}", "e\u{300}re", "ete"].map(str::to_string);
+        let entries = generate_entries(&lines, &[], false);
+
+        assert_eq!(
+            entries,
+            vec![
+                Entry {
+                    body: "e\u{301}te\u{301}".to_string(),
+                    key: None,
+                    prefix_len: "e\u{301}".len(),
+                    auto_accept: true
+                },
+                Entry {
+                    body: "e\u{300}re".to_string(),
+                    key: None,
+                    prefix_len: "e\u{300}".len(),
+                    auto_accept: true
+                },
+                Entry {
+                    body: "ete".to_string(),
+                    key: None,
+                    prefix_len: 1,
+                    auto_accept: true
+                },
+            ]
+        );
+        assert_eq!(entries[0].prefix(), "e\u{301}");
+    }
+
+    #[test]
+    fn test_generate_entries_wide_chars() {
+        let lines = ["日本語", "日本", "中文", "🦀 rust", "🦀 go"].map(str::to_string);
+        let entries = generate_entries(&lines, &[], false);
+
+        assert_eq!(
+            entries,
+            vec![
+                Entry {
+                    body: "日本語".to_string(),
+                    key: None,
+                    prefix_len: "日本語".len(),
+                    auto_accept: true
+                },
+                Entry {
+                    body: "日本".to_string(),
+                    key: None,
+                    prefix_len: "日本".len(),
+                    auto_accept: false
+                },
+                Entry {
+                    body: "中文".to_string(),
+                    key: None,
+                    prefix_len: "中".len(),
+                    auto_accept: true
+                },
+                Entry {
+                    body: "🦀 rust".to_string(),
+                    key: None,
+                    prefix_len: "🦀 r".len(),
+                    auto_accept: true
+                },
+                Entry {
+                    body: "🦀 go".to_string(),
+                    key: None,
+                    prefix_len: "🦀 g".len(),
+                    auto_accept: true
+                },
+            ]
+        );
+        assert_eq!(entries[3].prefix(), "🦀 r");
+    }
+
+    #[test]
+    fn test_marked_in_input_order() {
+        let lines = ["aaa", "bbb", "ccc", "ddd"].map(str::to_string);
+        let mut menu = Menu::from_lines(&lines, &[None, None, None, None], PREFIX_MATCHER);
+
+        menu.toggle_mark(3);
+        menu.toggle_mark(0);
+        menu.toggle_mark(2);
+        menu.toggle_mark(2);
+
+        assert_eq!(menu.marked().collect::<Vec<_>>(), vec![0, 3]);
+        assert!(menu.is_marked(0));
+        assert!(!menu.is_marked(2));
+    }
+
+    #[test]
+    fn test_split_explicit_key() {
+        assert_eq!(
+            split_explicit_key("[g] git status", None),
+            (Some("g".to_string()), "git status".to_string())
+        );
+        assert_eq!(
+            split_explicit_key("[gl]git log", None),
+            (Some("gl".to_string()), "git log".to_string())
+        );
+        assert_eq!(
+            split_explicit_key("g\tgit status", Some("\t")),
+            (Some("g".to_string()), "git status".to_string())
+        );
+        assert_eq!(
+            split_explicit_key("[] git status", None),
+            (None, "[] git status".to_string())
+        );
+        assert_eq!(
+            split_explicit_key("git status", None),
+            (None, "git status".to_string())
+        );
+        assert_eq!(
+            split_explicit_key("[g] git status", Some("\t")),
+            (None, "[g] git status".to_string())
+        );
+    }
+
+    #[test]
+    fn test_key_set_conflicts() {
+        let mut keys = KeySet::new(false);
+        assert_eq!(keys.insert("g"), Ok(()));
+        assert_eq!(keys.insert("ab"), Ok(()));
+        assert_eq!(keys.insert("b"), Ok(()));
+
+        let mut keys = KeySet::new(false);
+        assert_eq!(keys.insert("gs"), Ok(()));
+        assert_eq!(keys.insert("a"), Ok(()));
+        assert_eq!(keys.insert("g"), Err("gs"));
+        assert_eq!(keys.insert("gsx"), Err("gs"));
+
+        let mut keys = KeySet::new(false);
+        assert_eq!(keys.insert("x"), Ok(()));
+        assert_eq!(keys.insert("x"), Err("x"));
+    }
+
+    #[test]
+    fn test_generate_entries_taken_keys() {
+        let lines = ["abc", "bcd", "b", "", "", "grep", "git status"].map(str::to_string);
+        let keys = [None, None, None, Some("a"), Some("bc"), None, Some("g")]
+            .map(|key| key.map(str::to_string));
+        let menu = Menu::from_lines(&lines, &keys, PREFIX_MATCHER);
+        let entries = menu.entries();
+
+        assert_eq!(entries[0].prefix(), "ab");
+        assert_eq!(entries[1].prefix(), "bcd");
+        assert_eq!(entries[5].prefix(), "gr");
+        assert!(!entries[3].auto_accept);
+        assert!(!entries[4].auto_accept);
+        assert!(!entries[6].auto_accept);
+
+        // Typing any entry's target never accepts another entry on the way, and either
+        // accepts the entry itself or leaves it to be accepted manually.
+        for (idx, entry) in entries.iter().enumerate() {
+            let target = entry.target();
+
+            for end in 1..=target.len() {
+                let accepted = menu.find_acceptable(&target[..end]);
+                assert!(
+                    accepted.is_none() || accepted == Some(idx),
+                    "typing {:?} accepts entry {accepted:?} instead of {idx}",
+                    &target[..end]
+                );
+            }
+
+            assert!(
+                menu.find_acceptable(entry.prefix()) == Some(idx)
+                    || (!entry.auto_accept && entry.is_selectable(target, PREFIX_MATCHER)),
+                "entry {idx} can't be reached by typing"
+            );
+        }
+    }
+
+    #[test]
+    fn test_from_lines_explicit_keys() {
+        let lines = ["git status", "grep", "ls"].map(str::to_string);
+        let keys = [Some("s".to_string()), None, None];
+        let menu = Menu::from_lines(&lines, &keys, PREFIX_MATCHER);
+
+        assert_eq!(menu.entries()[0].prefix(), "s");
+        assert_eq!(menu.entries()[1].prefix(), "g");
+        assert_eq!(menu.entries()[2].prefix(), "l");
+        assert_eq!(menu.find_acceptable("s"), Some(0));
+        assert!(!menu.has_selectable("gi"));
+    }
+
+    #[test]
+    fn test_generate_hints_simple() {
+        assert_eq!(generate_hints(0, &['a', 's'], &[]), Vec::<String>::new());
+        assert_eq!(
+            generate_hints(3, &['a', 's', 'd'], &[]),
+            vec!["a", "s", "d"]
+        );
+        assert_eq!(generate_hints(2, &['a', 's', 'd'], &[]), vec!["a", "s"]);
+        assert_eq!(
+            generate_hints(4, &['a', 's'], &[]),
+            vec!["aa", "as", "sa", "ss"]
+        );
+        assert_eq!(generate_hints(3, &['a', 's'], &[]), vec!["aa", "as", "sa"]);
+    }
+
+    #[test]
+    fn test_generate_hints_taken_keys() {
+        assert_eq!(generate_hints(2, &['a', 's', 'd'], &["a"]), vec!["s", "d"]);
+        assert_eq!(
+            generate_hints(3, &['a', 's', 'd'], &["as"]),
+            vec!["aa", "ad", "sa"]
+        );
+        assert_eq!(
+            generate_hints(2, &['a', 's'], &["a", "s"]),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn test_find_acceptable_substring() {
+        let lines = ["feature/foo", "feature/bar", "main"].map(str::to_string);
+        let keys = [None, None, Some("x".to_string())];
+        let matcher = Matcher {
+            mode: MatchMode::Substring,
+            ..PREFIX_MATCHER
+        };
+        let menu = Menu::from_lines(&lines, &keys, matcher);
+
+        assert_eq!(menu.find_acceptable("feature"), None);
+        assert_eq!(menu.find_acceptable("b"), Some(1));
+        assert_eq!(menu.find_acceptable("oo"), Some(0));
+        assert_eq!(menu.find_acceptable("x"), Some(2));
+        assert!(!menu.has_selectable("main"));
+    }
+
+    #[test]
+    fn test_generate_entries_fold_case() {
+        let lines = ["Docs", "downloads", "Desktop"].map(str::to_string);
+        let prefixes: Vec<_> = generate_entries(&lines, &[], true)
+            .into_iter()
+            .map(|entry| entry.body[..entry.prefix_len].to_string())
+            .collect();
+        assert_eq!(prefixes, vec!["Doc", "dow", "De"]);
+
+        // The Kelvin sign takes up 3 bytes, but folds into a 1-byte 'k'.
+        let lines = ["\u{212A}ilo", "kilogram"].map(str::to_string);
+        let entries = generate_entries(&lines, &[], true);
+        assert_eq!(entries[0].prefix_len, "\u{212A}ilo".len());
+        assert_eq!(entries[1].prefix_len, "kilog".len());
+        assert!(!entries[0].auto_accept);
+    }
+
+    #[test]
+    fn test_key_set_fold_case() {
+        let mut keys = KeySet::new(false);
+        assert_eq!(keys.insert("G"), Ok(()));
+        assert_eq!(keys.insert("gs"), Ok(()));
+
+        let mut keys = KeySet::new(true);
+        assert_eq!(keys.insert("G"), Ok(()));
+        assert_eq!(keys.insert("gs"), Err("G"));
+        assert_eq!(keys.compared_keys().collect::<Vec<_>>(), vec!["g"]);
+    }
+
+    #[test]
+    fn test_find_acceptable_smart_case() {
+        let lines = ["Docs", "downloads"].map(str::to_string);
+        let matcher = Matcher {
+            case: CaseMatching::Smart,
+            ..PREFIX_MATCHER
+        };
+        let menu = Menu::from_lines(&lines, &[None, None], matcher);
+
+        assert_eq!(menu.find_acceptable("doc"), Some(0));
+        assert_eq!(menu.find_acceptable("Doc"), Some(0));
+        assert_eq!(menu.find_acceptable("dow"), Some(1));
+        assert!(!menu.has_selectable("Dow"));
+    }
+
+    #[test]
+    fn test_set_key() {
+        let lines = ["git status", "git log", "grep"].map(str::to_string);
+        let mut menu = Menu::from_lines(&lines, &[None, None, None], PREFIX_MATCHER);
+        assert_eq!(menu.entries()[0].prefix(), "git s");
+        assert_eq!(menu.entries()[2].prefix(), "gr");
+
+        menu.set_key(1, Some("l".to_string()));
+        assert_eq!(menu.entries()[0].prefix(), "gi");
+        assert_eq!(menu.entries()[1].prefix(), "l");
+        assert_eq!(menu.find_acceptable("l"), Some(1));
+
+        menu.set_key(1, None);
+        assert_eq!(menu.entries()[0].prefix(), "git s");
+        assert_eq!(menu.entries()[1].prefix(), "git l");
+    }
+
+    #[test]
+    fn test_push_lengthens_prefixes() {
+        let mut menu = Menu::from_lines(&["docs".to_string()], &[None], PREFIX_MATCHER);
+        assert_eq!(menu.entries()[0].prefix(), "");
+
+        menu.push("downloads".to_string(), None);
+        assert_eq!(menu.entries()[0].prefix(), "doc");
+        assert_eq!(menu.entries()[1].prefix(), "dow");
+
+        menu.push("doc".to_string(), None);
+        assert_eq!(menu.entries()[0].prefix(), "docs");
+        assert_eq!(menu.entries()[2].prefix(), "doc");
+        assert!(!menu.entries()[2].auto_accept);
+        assert_eq!(menu.find_acceptable("docs"), Some(0));
+    }
+
+    #[test]
+    fn test_select_end() {
+        let lines = ["aaa", "bbb", "abc", "ccc"].map(str::to_string);
+        let mut menu = Menu::from_lines(&lines, &[None, None, None, None], PREFIX_MATCHER);
+
+        assert!(menu.select_end("", SearchDirection::Backwards));
+        assert_eq!(menu.selection(), Some(&3));
+        assert!(menu.select_end("a", SearchDirection::Backwards));
+        assert_eq!(menu.selection(), Some(&2));
+        assert!(menu.select_end("a", SearchDirection::Forwards));
+        assert_eq!(menu.selection(), Some(&0));
+        assert!(!menu.select_end("a", SearchDirection::Forwards));
+        assert!(!menu.select_end("x", SearchDirection::Forwards));
+    }
 }
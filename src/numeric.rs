@@ -14,6 +14,18 @@ pub fn wrapping_dec(n: usize, max: usize) -> (usize, bool) {
     }
 }
 
+/// Returns the smallest change to `offset` such that `target` lies within a window of `len`
+/// elements starting at it.
+pub fn scroll_to_fit(offset: usize, target: usize, len: usize) -> usize {
+    if target < offset {
+        target
+    } else if target >= offset + len {
+        target + 1 - len
+    } else {
+        offset
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -45,4 +57,19 @@ mod tests {
         assert_eq!(wrapping_dec(0, 1), (0, true));
         assert_eq!(wrapping_dec(0, 91), (90, true));
     }
+
+    #[test]
+    fn test_scroll_to_fit_inside() {
+        assert_eq!(scroll_to_fit(0, 0, 5), 0);
+        assert_eq!(scroll_to_fit(0, 4, 5), 0);
+        assert_eq!(scroll_to_fit(3, 5, 5), 3);
+    }
+
+    #[test]
+    fn test_scroll_to_fit_outside() {
+        assert_eq!(scroll_to_fit(0, 5, 5), 1);
+        assert_eq!(scroll_to_fit(0, 12, 5), 8);
+        assert_eq!(scroll_to_fit(6, 2, 5), 2);
+        assert_eq!(scroll_to_fit(6, 0, 1), 0);
+    }
 }
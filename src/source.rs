@@ -0,0 +1,215 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, ErrorKind, Read},
+    process::{Child, ChildStdout, Command, ExitStatus, Stdio},
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+};
+
+use crate::cli::{Args, CliError};
+
+/// Output of a shell command, which is killed if it's still running once dropped.
+struct CommandOutput {
+    child: Child,
+    stdout: ChildStdout,
+}
+
+impl Read for CommandOutput {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.stdout.read(buf)
+    }
+}
+
+impl Drop for CommandOutput {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Sends every line of `reader` through `sender`, stopping at the first error. Returns
+/// whether all of them were read and sent.
+fn send_lines(reader: impl Read, read0: bool, sender: &Sender<Result<String, CliError>>) -> bool {
+    let reader = BufReader::new(reader);
+    let lines: Box<dyn Iterator<Item = io::Result<String>>> = if read0 {
+        Box::new(reader.split(b'\0').map(|line| {
+            String::from_utf8(line?).map_err(|err| io::Error::new(ErrorKind::InvalidData, err))
+        }))
+    } else {
+        Box::new(reader.lines())
+    };
+
+    for line in lines {
+        let is_err = line.is_err();
+
+        // The receiver is only dropped once the menu is closed.
+        if sender.send(line.map_err(CliError::from)).is_err() || is_err {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Reads lines out of `reader` on a separate thread, so that the menu can be shown while
+/// they're still coming in. Lines are delimited by NUL characters instead of newlines if
+/// `read0` is set. The channel is closed once there are no lines left, or right after an
+/// error.
+pub fn spawn_line_reader(
+    reader: impl Read + Send + 'static,
+    read0: bool,
+) -> Receiver<Result<String, CliError>> {
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || send_lines(reader, read0, &sender));
+
+    receiver
+}
+
+/// Explains why a command failed, going by the last line it printed to stderr if any.
+fn failure_reason(status: ExitStatus, stderr: &[u8]) -> String {
+    let stderr = String::from_utf8_lossy(stderr);
+
+    match stderr.lines().map(str::trim).rfind(|line| !line.is_empty()) {
+        Some(line) => format!("{line} ({status})"),
+        None => status.to_string(),
+    }
+}
+
+/// Runs `command` through `sh` and reads lines out of its output, like `spawn_line_reader`.
+/// The command's stdin is discarded and its stderr is kept out of the menu, but an error is
+/// sent along with the end of its stderr if it fails.
+pub fn spawn_command_reader(
+    command: &str,
+    read0: bool,
+) -> io::Result<Receiver<Result<String, CliError>>> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let stdout = child.stdout.take().unwrap();
+    let mut stderr = child.stderr.take().unwrap();
+
+    let mut output = CommandOutput { child, stdout };
+    let command = command.to_string();
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        // Read separately, so that the command can't get stuck on a full stderr pipe.
+        let stderr = thread::spawn(move || {
+            let mut buf = Vec::new();
+            let _ = stderr.read_to_end(&mut buf);
+            buf
+        });
+
+        if !send_lines(&mut output, read0, &sender) {
+            return;
+        }
+
+        let result = match output.child.wait() {
+            Ok(status) if status.success() => return,
+            Ok(status) => {
+                let stderr = stderr.join().unwrap_or_default();
+                Err(CliError::Command(command, failure_reason(status, &stderr)))
+            }
+            Err(err) => Err(err.into()),
+        };
+
+        let _ = sender.send(result);
+    });
+
+    Ok(receiver)
+}
+
+/// Reads lines out of wherever `args` says entries come from, which is stdin by default.
+pub fn spawn_input_reader(args: &Args) -> io::Result<Receiver<Result<String, CliError>>> {
+    if let Some(path) = &args.input_file {
+        let file = File::open(path)
+            .map_err(|err| io::Error::new(err.kind(), format!("{}: {err}", path.display())))?;
+        Ok(spawn_line_reader(file, args.read0))
+    } else if let Some(command) = &args.command {
+        spawn_command_reader(command, args.read0)
+    } else {
+        Ok(spawn_line_reader(io::stdin(), args.read0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Cursor, path::Path};
+
+    use super::*;
+
+    fn read_all(receiver: Receiver<Result<String, CliError>>) -> Vec<Result<String, String>> {
+        receiver
+            .iter()
+            .map(|line| line.map_err(|err| err.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_spawn_line_reader() {
+        let lines =
+            |input: &'static [u8], read0| read_all(spawn_line_reader(Cursor::new(input), read0));
+
+        assert_eq!(
+            lines(b"foo\nbar baz\n", false),
+            [Ok("foo".to_string()), Ok("bar baz".to_string())]
+        );
+        assert_eq!(
+            lines(b"foo\nbar\0baz", true),
+            [Ok("foo\nbar".to_string()), Ok("baz".to_string())]
+        );
+        assert_eq!(lines(b"", false), []);
+
+        let invalid = lines(b"foo\0\xff\0bar", true);
+        assert_eq!(invalid.len(), 2);
+        assert!(invalid[1].is_err());
+    }
+
+    #[test]
+    fn test_spawn_command_reader() {
+        let lines = |command, read0| read_all(spawn_command_reader(command, read0).unwrap());
+
+        assert_eq!(
+            lines(r#"printf 'a\0b'"#, true),
+            [Ok("a".to_string()), Ok("b".to_string())]
+        );
+        assert_eq!(
+            lines("echo a; echo warning >&2", false),
+            [Ok("a".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_spawn_command_reader_failure() {
+        let command = "echo a; echo oops >&2; exit 3";
+        let lines = read_all(spawn_command_reader(command, false).unwrap());
+
+        assert_eq!(lines[0], Ok("a".to_string()));
+        assert_eq!(
+            lines[1],
+            Err(format!("command {command:?} failed: oops (exit status: 3)"))
+        );
+        assert_eq!(lines.len(), 2);
+    }
+
+    #[test]
+    fn test_command_output_killed_on_drop() {
+        let mut child = Command::new("sleep")
+            .arg("10")
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let pid = child.id();
+
+        drop(CommandOutput { child, stdout });
+
+        // Reaped right away, so there's no process left, not even a zombie.
+        assert!(!Path::new(&format!("/proc/{pid}")).exists());
+    }
+}
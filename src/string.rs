@@ -1,3 +1,11 @@
+use std::{borrow::Cow, mem, ops::Range};
+
+use crossterm::style::{StyledContent, Stylize};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+const ELLIPSIS: &str = "…";
+
 pub fn delete_word(s: &str) -> String {
     s.trim_end()
         .rsplit_once(' ')
@@ -5,6 +13,165 @@ pub fn delete_word(s: &str) -> String {
         .unwrap_or_default()
 }
 
+/// Replaces line breaks with visible symbols, so that multi-line entries take up a single
+/// row.
+pub fn show_line_breaks(s: &str) -> Cow<'_, str> {
+    if s.contains(['\n', '\r']) {
+        Cow::Owned(s.replace('\n', "␤").replace('\r', "␍"))
+    } else {
+        Cow::Borrowed(s)
+    }
+}
+
+fn total_width(segments: &[StyledContent<String>]) -> usize {
+    segments.iter().map(|el| el.content().width()).sum()
+}
+
+fn ellipsis() -> StyledContent<String> {
+    ELLIPSIS.to_string().stylize()
+}
+
+/// Returns the part of `segments` between columns `start` and `end`. Graphemes that
+/// straddle either bound are left out.
+fn slice_columns(
+    segments: &[StyledContent<String>],
+    start: usize,
+    end: usize,
+) -> Vec<StyledContent<String>> {
+    let mut col = 0;
+    let mut sliced = Vec::new();
+
+    for el in segments {
+        let mut content = String::new();
+
+        for grapheme in el.content().graphemes(true) {
+            let next_col = col + grapheme.width();
+
+            if col >= start && next_col <= end {
+                content.push_str(grapheme);
+            }
+            col = next_col;
+        }
+
+        if !content.is_empty() {
+            sliced.push(StyledContent::new(*el.style(), content));
+        }
+
+        if col >= end {
+            break;
+        }
+    }
+
+    sliced
+}
+
+/// Cuts `segments` short so that they fit within `max_width` columns, ending them with an
+/// ellipsis if anything had to be left out. The ellipsis is dropped rather than covering
+/// any of the first `keep_width` columns.
+pub fn truncate_segments(
+    segments: Vec<StyledContent<String>>,
+    max_width: usize,
+    keep_width: usize,
+) -> Vec<StyledContent<String>> {
+    if total_width(&segments) <= max_width {
+        return segments;
+    }
+
+    let ellipsis_width = ELLIPSIS.width();
+
+    if max_width < keep_width + ellipsis_width {
+        return slice_columns(&segments, 0, max_width);
+    }
+
+    let mut truncated = slice_columns(&segments, 0, max_width - ellipsis_width);
+    truncated.push(ellipsis());
+    truncated
+}
+
+/// Fits `segments` within `max_width` columns by showing a window of them, centred on the
+/// columns in `focus`. Ellipses mark the parts left out on either side.
+pub fn scroll_segments(
+    segments: Vec<StyledContent<String>>,
+    focus: Range<usize>,
+    max_width: usize,
+) -> Vec<StyledContent<String>> {
+    let total_width = total_width(&segments);
+    let ellipsis_width = ELLIPSIS.width();
+
+    if total_width <= max_width {
+        return segments;
+    } else if max_width <= 2 * ellipsis_width {
+        return truncate_segments(segments, max_width, 0);
+    }
+
+    // The start of the focus is kept in view even if all of it doesn't fit.
+    let centre = (focus.start + focus.end) / 2;
+    let start = centre
+        .saturating_sub(max_width / 2)
+        .min(focus.start.saturating_sub(ellipsis_width))
+        .min(total_width - max_width);
+    let end = start + max_width;
+
+    let mut scrolled = Vec::new();
+    let mut inner = start..end;
+
+    if start > 0 {
+        scrolled.push(ellipsis());
+        inner.start += ellipsis_width;
+    }
+    if end < total_width {
+        inner.end -= ellipsis_width;
+    }
+
+    scrolled.extend(slice_columns(&segments, inner.start, inner.end));
+
+    if end < total_width {
+        scrolled.push(ellipsis());
+    }
+
+    scrolled
+}
+
+/// Splits `segments` into lines of at most `max_width` columns each. Graphemes wider than
+/// a whole line are left out.
+pub fn wrap_segments(
+    segments: Vec<StyledContent<String>>,
+    max_width: usize,
+) -> Vec<Vec<StyledContent<String>>> {
+    let mut lines = vec![Vec::new()];
+    let mut col = 0;
+
+    for el in segments {
+        let mut content = String::new();
+
+        for grapheme in el.content().graphemes(true) {
+            let width = grapheme.width();
+
+            if col + width > max_width && col > 0 {
+                if !content.is_empty() {
+                    let line = lines.last_mut().unwrap();
+                    line.push(StyledContent::new(*el.style(), mem::take(&mut content)));
+                }
+
+                lines.push(Vec::new());
+                col = 0;
+            }
+
+            if width <= max_width {
+                content.push_str(grapheme);
+                col += width;
+            }
+        }
+
+        if !content.is_empty() {
+            let line = lines.last_mut().unwrap();
+            line.push(StyledContent::new(*el.style(), content));
+        }
+    }
+
+    lines
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -16,4 +183,70 @@ mod tests {
         assert_eq!(delete_word("   "), String::new());
         assert_eq!(delete_word("#.- {}()   "), "#.- ".to_string());
     }
+
+    fn contents(segments: &[StyledContent<String>]) -> Vec<&str> {
+        segments.iter().map(|el| el.content().as_str()).collect()
+    }
+
+    fn segments() -> Vec<StyledContent<String>> {
+        vec!["foo".to_string().bold(), "bar baz".to_string().stylize()]
+    }
+
+    #[test]
+    fn test_truncate_segments() {
+        let truncate = |max_width, keep_width| truncate_segments(segments(), max_width, keep_width);
+
+        assert_eq!(contents(&truncate(10, 0)), ["foo", "bar baz"]);
+        assert_eq!(contents(&truncate(6, 0)), ["foo", "ba", "…"]);
+        assert_eq!(contents(&truncate(4, 0)), ["foo", "…"]);
+        assert_eq!(contents(&truncate(3, 0)), ["fo", "…"]);
+        assert_eq!(contents(&truncate(1, 0)), ["…"]);
+        assert!(truncate(0, 0).is_empty());
+        assert_eq!(*truncate(3, 0)[0].style(), *segments()[0].style());
+    }
+
+    #[test]
+    fn test_truncate_segments_keeps_prefix() {
+        let truncate = |max_width, keep_width| truncate_segments(segments(), max_width, keep_width);
+
+        assert_eq!(contents(&truncate(4, 3)), ["foo", "…"]);
+        assert_eq!(contents(&truncate(3, 3)), ["foo"]);
+        assert_eq!(contents(&truncate(2, 3)), ["fo"]);
+    }
+
+    #[test]
+    fn test_scroll_segments() {
+        let scroll = |focus, max_width| scroll_segments(segments(), focus, max_width);
+
+        assert_eq!(contents(&scroll(7..10, 10)), ["foo", "bar baz"]);
+        assert_eq!(contents(&scroll(0..2, 6)), ["foo", "ba", "…"]);
+        assert_eq!(contents(&scroll(7..10, 6)), ["…", "r baz"]);
+        assert_eq!(contents(&scroll(4..5, 6)), ["…", "o", "bar", "…"]);
+        assert_eq!(contents(&scroll(4..5, 2)), ["f", "…"]);
+    }
+
+    #[test]
+    fn test_wrap_segments() {
+        let wrap = |max_width| {
+            wrap_segments(segments(), max_width)
+                .iter()
+                .map(|line| contents(line).join("|"))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(wrap(10), ["foo|bar baz"]);
+        assert_eq!(wrap(4), ["foo|b", "ar b", "az"]);
+        assert_eq!(wrap(3), ["foo", "bar", " ba", "z"]);
+        assert_eq!(
+            wrap_segments(vec!["日本語".to_string().stylize()], 3).len(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_show_line_breaks() {
+        assert_eq!(show_line_breaks("foo bar"), "foo bar");
+        assert_eq!(show_line_breaks("foo\nbar\n"), "foo␤bar␤");
+        assert_eq!(show_line_breaks("foo\r\nbar"), "foo␍␤bar");
+    }
 }
@@ -0,0 +1,190 @@
+use crossterm::style::{Attribute, Color, ContentStyle};
+
+use crate::{
+    cli::{Args, Theme},
+    color::ColorDepth,
+};
+
+/// The style of every highlighted part of the menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Highlights {
+    pub prefix: ContentStyle,
+    pub input_overlay: ContentStyle,
+    pub disabled_entry: ContentStyle,
+    pub indicator: ContentStyle,
+    pub marker: ContentStyle,
+}
+
+fn style(foreground_color: Option<Color>, attributes: &[Attribute]) -> ContentStyle {
+    let mut style = ContentStyle {
+        foreground_color,
+        ..ContentStyle::new()
+    };
+
+    for &attr in attributes {
+        style.attributes.set(attr);
+    }
+
+    style
+}
+
+/// Adapts the colors of `style` to `depth`. Styles that lose their colors entirely also get the
+/// attributes of `fallback`, so that they can still be told apart.
+fn downgrade_style(style: ContentStyle, depth: ColorDepth, fallback: ContentStyle) -> ContentStyle {
+    let has_colors = style.foreground_color.is_some() || style.background_color.is_some();
+    let mut downgraded = ContentStyle {
+        foreground_color: style
+            .foreground_color
+            .and_then(|color| depth.downgrade(color)),
+        background_color: style
+            .background_color
+            .and_then(|color| depth.downgrade(color)),
+        underline_color: style
+            .underline_color
+            .and_then(|color| depth.downgrade(color)),
+        attributes: style.attributes,
+    };
+
+    if has_colors && downgraded.foreground_color.is_none() && downgraded.background_color.is_none()
+    {
+        downgraded.attributes.extend(fallback.attributes);
+    }
+
+    downgraded
+}
+
+impl Highlights {
+    pub fn from_theme(theme: Theme) -> Self {
+        match theme {
+            Theme::Dark => Self {
+                prefix: style(Some(Color::AnsiValue(110)), &[Attribute::Bold]),
+                input_overlay: style(Some(Color::AnsiValue(204)), &[Attribute::Bold]),
+                disabled_entry: style(Some(Color::AnsiValue(242)), &[]),
+                indicator: style(Some(Color::AnsiValue(204)), &[]),
+                marker: style(Some(Color::AnsiValue(114)), &[]),
+            },
+            Theme::Light => Self {
+                prefix: style(Some(Color::AnsiValue(25)), &[Attribute::Bold]),
+                input_overlay: style(Some(Color::AnsiValue(161)), &[Attribute::Bold]),
+                disabled_entry: style(Some(Color::AnsiValue(248)), &[]),
+                indicator: style(Some(Color::AnsiValue(161)), &[]),
+                marker: style(Some(Color::AnsiValue(28)), &[]),
+            },
+            Theme::Mono => Self {
+                prefix: style(None, &[Attribute::Bold, Attribute::Underlined]),
+                input_overlay: style(None, &[Attribute::Bold, Attribute::Reverse]),
+                disabled_entry: style(None, &[Attribute::Dim]),
+                indicator: style(None, &[Attribute::Bold]),
+                marker: style(None, &[Attribute::Bold]),
+            },
+            Theme::SixteenColor => Self {
+                prefix: style(Some(Color::DarkBlue), &[Attribute::Bold]),
+                input_overlay: style(Some(Color::DarkRed), &[Attribute::Bold]),
+                disabled_entry: style(Some(Color::DarkGrey), &[]),
+                indicator: style(Some(Color::DarkRed), &[]),
+                marker: style(Some(Color::DarkGreen), &[]),
+            },
+        }
+    }
+
+    /// The highlights of the chosen theme, overridden by the individual --hl-* options.
+    pub fn new(args: &Args) -> Self {
+        let theme = Self::from_theme(args.theme);
+
+        Self {
+            prefix: args.hl_prefix.unwrap_or(theme.prefix),
+            input_overlay: args.hl_input_overlay.unwrap_or(theme.input_overlay),
+            disabled_entry: args.hl_disabled_entry.unwrap_or(theme.disabled_entry),
+            indicator: args.hl_indicator.unwrap_or(theme.indicator),
+            marker: args.hl_marker.unwrap_or(theme.marker),
+        }
+    }
+
+    /// Adapts every highlight to what the terminal can show, falling back to the attributes
+    /// of the mono theme when colors aren't available.
+    pub fn downgrade(self, depth: ColorDepth) -> Self {
+        let mono = Self::from_theme(Theme::Mono);
+
+        Self {
+            prefix: downgrade_style(self.prefix, depth, mono.prefix),
+            input_overlay: downgrade_style(self.input_overlay, depth, mono.input_overlay),
+            disabled_entry: downgrade_style(self.disabled_entry, depth, mono.disabled_entry),
+            indicator: downgrade_style(self.indicator, depth, mono.indicator),
+            marker: downgrade_style(self.marker, depth, mono.marker),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    #[test]
+    fn test_highlights_overrides() {
+        let args = Args::parse_from(["ppick", "--theme", "mono", "--hl-marker", "fg=green"]);
+        let highlights = Highlights::new(&args);
+        let mono = Highlights::from_theme(Theme::Mono);
+
+        assert_eq!(highlights.prefix, mono.prefix);
+        assert_eq!(highlights.indicator, mono.indicator);
+        assert_eq!(highlights.marker, style(Some(Color::DarkGreen), &[]));
+    }
+
+    #[test]
+    fn test_downgrade_without_colors() {
+        let highlights = Highlights::from_theme(Theme::SixteenColor).downgrade(ColorDepth::None);
+
+        assert_eq!(
+            highlights.prefix,
+            style(None, &[Attribute::Bold, Attribute::Underlined])
+        );
+        assert_eq!(
+            highlights.input_overlay,
+            style(None, &[Attribute::Bold, Attribute::Reverse])
+        );
+        assert_ne!(highlights.prefix, highlights.input_overlay);
+    }
+
+    #[test]
+    fn test_downgrade_keeps_attributes() {
+        let args = Args::parse_from(["ppick", "--hl-prefix", "italic bg=#202020"]);
+        let highlights = Highlights::new(&args).downgrade(ColorDepth::Ansi256);
+
+        assert_eq!(
+            highlights.prefix,
+            ContentStyle {
+                background_color: Some(Color::AnsiValue(234)),
+                ..style(None, &[Attribute::Italic])
+            }
+        );
+    }
+
+    #[test]
+    fn test_mono_theme_has_no_colors() {
+        let mono = Highlights::from_theme(Theme::Mono);
+
+        for highlight in [
+            mono.prefix,
+            mono.input_overlay,
+            mono.disabled_entry,
+            mono.indicator,
+            mono.marker,
+        ] {
+            assert_eq!(highlight.foreground_color, None);
+            assert_eq!(highlight.background_color, None);
+        }
+    }
+
+    #[test]
+    fn test_disabled_entries_stand_out_without_colors() {
+        let highlights = Highlights::from_theme(Theme::SixteenColor).downgrade(ColorDepth::None);
+
+        assert_eq!(highlights.disabled_entry, style(None, &[Attribute::Dim]));
+        assert_ne!(
+            Highlights::from_theme(Theme::Mono).disabled_entry,
+            ContentStyle::new()
+        );
+    }
+}
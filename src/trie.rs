@@ -0,0 +1,298 @@
+use std::{borrow::Cow, collections::HashMap};
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::matcher;
+
+const ROOT: usize = 0;
+
+#[derive(Debug, Clone, Default)]
+struct Node {
+    /// Child nodes, by the (possibly case-folded) grapheme cluster leading to them.
+    children: HashMap<String, usize>,
+    /// Amount of texts going through this node, including those that end at it.
+    count: usize,
+    /// Items whose text ends at this node.
+    items: Vec<usize>,
+}
+
+/// Trie of texts, split into grapheme clusters, where each text is identified by an item
+/// index. Texts are compared case-insensitively if `fold_case` is set.
+#[derive(Debug, Clone)]
+pub struct PrefixTrie {
+    fold_case: bool,
+    nodes: Vec<Node>,
+    /// Nodes that were pruned and can be reused.
+    free_nodes: Vec<usize>,
+}
+
+impl PrefixTrie {
+    pub fn new(fold_case: bool) -> Self {
+        Self {
+            fold_case,
+            nodes: vec![Node::default()],
+            free_nodes: Vec::new(),
+        }
+    }
+
+    pub fn fold_case(&self) -> bool {
+        self.fold_case
+    }
+
+    fn edge<'a>(&self, grapheme: &'a str) -> Cow<'a, str> {
+        // Most graphemes are left as they are by folding, so avoid allocating for them.
+        let is_folded = || {
+            grapheme.chars().all(|ch| {
+                let mut lowercase = ch.to_lowercase();
+                lowercase.next() == Some(ch) && lowercase.next().is_none()
+            })
+        };
+
+        if self.fold_case && !is_folded() {
+            Cow::Owned(matcher::fold_case(grapheme))
+        } else {
+            Cow::Borrowed(grapheme)
+        }
+    }
+
+    fn child(&self, node: usize, grapheme: &str) -> Option<usize> {
+        self.nodes[node]
+            .children
+            .get(self.edge(grapheme).as_ref())
+            .copied()
+    }
+
+    /// Returns the only item below `node`, which must hold exactly one.
+    fn lone_item(&self, mut node: usize) -> usize {
+        loop {
+            match self.nodes[node].items[..] {
+                [item] => break item,
+                _ => node = *self.nodes[node].children.values().next().unwrap(),
+            }
+        }
+    }
+
+    /// Adds `text` as `item`. Returns the one other item whose prefix may have grown
+    /// because of it, if any.
+    pub fn insert(&mut self, text: &str, item: usize) -> Option<usize> {
+        let mut node = ROOT;
+        let mut affected = None;
+
+        for grapheme in text.graphemes(true) {
+            // The first node that used to lead to a single item is where that item's
+            // prefix ended, and the new text now goes past it.
+            if affected.is_none() && self.nodes[node].count == 1 {
+                affected = Some(self.lone_item(node));
+            }
+            self.nodes[node].count += 1;
+
+            node = match self.child(node, grapheme) {
+                Some(child) => child,
+                None => {
+                    let child = self.free_nodes.pop().unwrap_or_else(|| {
+                        self.nodes.push(Node::default());
+                        self.nodes.len() - 1
+                    });
+                    let edge = self.edge(grapheme).into_owned();
+                    self.nodes[node].children.insert(edge, child);
+                    child
+                }
+            };
+        }
+
+        if affected.is_none() && self.nodes[node].count == 1 {
+            affected = Some(self.lone_item(node));
+        }
+        self.nodes[node].count += 1;
+        self.nodes[node].items.push(item);
+
+        affected
+    }
+
+    /// Removes `text`, which must have been added as `item`. Returns the one other item
+    /// whose prefix may have shrunk because of it, if any.
+    pub fn remove(&mut self, text: &str, item: usize) -> Option<usize> {
+        let mut path = vec![ROOT];
+
+        for grapheme in text.graphemes(true) {
+            let node = self.child(*path.last().unwrap(), grapheme).unwrap();
+            path.push(node);
+        }
+
+        let last = *path.last().unwrap();
+        let items = &mut self.nodes[last].items;
+        let position = items.iter().position(|&other| other == item).unwrap();
+        items.swap_remove(position);
+
+        for (depth, &node) in path.iter().enumerate() {
+            self.nodes[node].count -= 1;
+
+            if self.nodes[node].count == 0 && node != ROOT {
+                // Nothing below this node anymore, so it can go along with its descendants.
+                let parent = path[depth - 1];
+                self.nodes[parent]
+                    .children
+                    .retain(|_, &mut child| child != node);
+
+                for &pruned in &path[depth..] {
+                    self.nodes[pruned].count = 0;
+                    self.nodes[pruned].children.clear();
+                    self.free_nodes.push(pruned);
+                }
+                break;
+            }
+        }
+
+        path.into_iter()
+            .find(|&node| self.nodes[node].count == 1)
+            .map(|node| self.lone_item(node))
+    }
+
+    /// Returns the length in bytes of the shortest prefix of `text` that no other text
+    /// starts with, and whether there is one at all. Otherwise, the whole text is returned,
+    /// since other texts start with it. `text` must have been added to the trie.
+    pub fn unique_prefix_len(&self, text: &str) -> (usize, bool) {
+        let mut node = ROOT;
+        let mut len = 0;
+
+        if self.nodes[node].count == 1 {
+            return (0, true);
+        }
+
+        for grapheme in text.graphemes(true) {
+            node = self.child(node, grapheme).unwrap();
+            len += grapheme.len();
+
+            if self.nodes[node].count == 1 {
+                return (len, true);
+            }
+        }
+
+        (text.len(), false)
+    }
+
+    /// Returns the node reached by following `input` exactly, grapheme by grapheme.
+    fn find_node(&self, input: &str) -> Option<usize> {
+        input
+            .graphemes(true)
+            .try_fold(ROOT, |node, grapheme| self.child(node, grapheme))
+    }
+
+    /// Whether any text starts with `input`.
+    pub fn has_prefixed(&self, input: &str) -> bool {
+        let Some((last, init)) = input
+            .grapheme_indices(true)
+            .next_back()
+            .map(|(idx, last)| (last, &input[..idx]))
+        else {
+            return self.nodes[ROOT].count > 0;
+        };
+
+        // The last grapheme of the input may be unfinished, like an 'e' about to get an
+        // accent, so it only has to start an edge.
+        let last = self.edge(last);
+        self.find_node(init).is_some_and(|node| {
+            self.nodes[node]
+                .children
+                .keys()
+                .any(|edge| edge.starts_with(last.as_ref()))
+        })
+    }
+
+    /// Returns the items that may have `input` as their prefix: those whose text is exactly
+    /// `input`, and the only item whose text starts with it, if there's just one.
+    pub fn prefix_candidates(&self, input: &str) -> Vec<usize> {
+        let Some(node) = self.find_node(input) else {
+            return Vec::new();
+        };
+
+        let mut candidates = self.nodes[node].items.clone();
+
+        if self.nodes[node].count == 1 && candidates.is_empty() {
+            candidates.push(self.lone_item(node));
+        }
+
+        candidates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_affected() {
+        let mut trie = PrefixTrie::new(false);
+        assert_eq!(trie.insert("docs", 0), None);
+        assert_eq!(trie.insert("downloads", 1), Some(0));
+        assert_eq!(trie.insert("desktop", 2), None);
+        assert_eq!(trie.insert("doc", 3), Some(0));
+        assert_eq!(trie.insert("music", 4), None);
+
+        assert_eq!(trie.unique_prefix_len("docs"), (4, true));
+        assert_eq!(trie.unique_prefix_len("doc"), (3, false));
+        assert_eq!(trie.unique_prefix_len("downloads"), (3, true));
+        assert_eq!(trie.unique_prefix_len("desktop"), (2, true));
+        assert_eq!(trie.unique_prefix_len("music"), (1, true));
+    }
+
+    #[test]
+    fn test_remove_affected() {
+        let mut trie = PrefixTrie::new(false);
+        trie.insert("docs", 0);
+        trie.insert("doc", 1);
+        trie.insert("music", 2);
+
+        assert_eq!(trie.remove("docs", 0), Some(1));
+        assert_eq!(trie.unique_prefix_len("doc"), (1, true));
+        assert_eq!(trie.remove("music", 2), Some(1));
+        assert_eq!(trie.unique_prefix_len("doc"), (0, true));
+
+        // Pruned nodes get reused.
+        let node_count = trie.nodes.len();
+        trie.insert("music", 2);
+        assert_eq!(trie.nodes.len(), node_count);
+        assert_eq!(trie.unique_prefix_len("music"), (1, true));
+    }
+
+    #[test]
+    fn test_remove_duplicate() {
+        let mut trie = PrefixTrie::new(false);
+        trie.insert("aaa", 0);
+        trie.insert("aaa", 1);
+        assert_eq!(trie.unique_prefix_len("aaa"), (3, false));
+
+        assert_eq!(trie.remove("aaa", 0), Some(1));
+        assert_eq!(trie.prefix_candidates(""), vec![1]);
+    }
+
+    #[test]
+    fn test_has_prefixed() {
+        let mut trie = PrefixTrie::new(true);
+        assert!(!trie.has_prefixed(""));
+
+        trie.insert("E\u{301}te\u{301}", 0);
+        trie.insert("Kelvin", 1);
+
+        assert!(trie.has_prefixed(""));
+        assert!(trie.has_prefixed("e"));
+        assert!(trie.has_prefixed("e\u{301}t"));
+        assert!(trie.has_prefixed("\u{212A}el"));
+        assert!(!trie.has_prefixed("e\u{300}"));
+        assert!(!trie.has_prefixed("kelvins"));
+    }
+
+    #[test]
+    fn test_prefix_candidates() {
+        let mut trie = PrefixTrie::new(false);
+        trie.insert("g", 0);
+        trie.insert("git", 1);
+        trie.insert("ls", 2);
+
+        assert_eq!(trie.prefix_candidates("g"), vec![0]);
+        assert_eq!(trie.prefix_candidates("gi"), vec![1]);
+        assert_eq!(trie.prefix_candidates("l"), vec![2]);
+        assert_eq!(trie.prefix_candidates(""), Vec::<usize>::new());
+        assert_eq!(trie.prefix_candidates("x"), Vec::<usize>::new());
+    }
+}